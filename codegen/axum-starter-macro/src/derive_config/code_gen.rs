@@ -1,7 +1,12 @@
 use darling::{util::Override, ToTokens};
+use heck::{ToKebabCase, ToShoutySnakeCase};
 use syn::{Expr, Path, Type};
 
-use super::derive_inputs::{Address, DeriveInput, Logger, Provider};
+use crate::utils::option_inner;
+
+use super::derive_inputs::{
+    Address, ConfField, DeriveInput, Logger, ProvidedFields, Provider, SubscriberLogger,
+};
 
 pub struct ImplAddress<'r> {
     ident: &'r syn::Ident,
@@ -82,45 +87,140 @@ impl<'r> ToTokens for ImplAddress<'r> {
 
 pub struct ImplInitLog<'r> {
     ident: &'r syn::Ident,
-    err_type: &'r Type,
-    init: &'r Expr,
-    associate: bool,
+    init: InitLog<'r>,
 }
 
-impl<'r> From<&'r DeriveInput> for Option<ImplInitLog<'r>> {
-    fn from(input: &'r DeriveInput) -> Self {
-        let Logger {
+enum InitLog<'r> {
+    Func {
+        err_type: &'r Type,
+        init: &'r Expr,
+        associate: bool,
+    },
+    Subscriber {
+        subscriber: Option<&'r SubscriberLogger>,
+        /// the `Provider::provide` of `level_from` field
+        level_from: Option<proc_macro2::TokenStream>,
+        /// the `Provider::provide` of `format_from` field
+        format_from: Option<proc_macro2::TokenStream>,
+    },
+}
+
+impl<'r> ImplInitLog<'r> {
+    pub fn new(input: &'r DeriveInput, provided: &ProvidedFields) -> darling::Result<Option<Self>> {
+        let Some(Logger {
             func,
             error,
             associate,
-        } = input.logger.as_ref()?;
+            tracing_subscriber,
+        }) = input.logger.as_ref()
+        else {
+            return Ok(None);
+        };
+
+        let provide = |field: &Option<syn::Ident>| {
+            field
+                .as_ref()
+                .map(|field| {
+                    input.field(field)?;
+                    provided.provide_field(field)
+                })
+                .transpose()
+        };
+        let init = match (func, error, tracing_subscriber) {
+            (_, _, Some(Override::Inherit)) => InitLog::Subscriber {
+                subscriber: None,
+                level_from: None,
+                format_from: None,
+            },
+            (_, _, Some(Override::Explicit(subscriber))) => InitLog::Subscriber {
+                subscriber: Some(subscriber),
+                level_from: provide(&subscriber.level_from)?,
+                format_from: provide(&subscriber.format_from)?,
+            },
+            (Some(init), Some(err_type), None) => InitLog::Func {
+                err_type,
+                init,
+                associate: *associate,
+            },
+            // checked by `Logger`
+            _ => return Ok(None),
+        };
 
-        Some(ImplInitLog {
+        Ok(Some(ImplInitLog {
             ident: &input.ident,
-            err_type: error,
-            init: func,
-            associate: *associate,
-        })
+            init,
+        }))
     }
 }
+
 impl<'r> ToTokens for ImplInitLog<'r> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-        let ImplInitLog {
-            err_type,
-            init,
-            ident,
-            associate,
-        } = self;
+        let ImplInitLog { init, ident } = self;
 
-        let call = if *associate {
-            quote::quote!((#init)())
-        } else {
-            quote::quote!(
-                fn __fetcher()
-                -> impl Fn(&#ident) -> ::core::result::Result<(), #err_type>{
-                    #init
-                }
-                ( __fetcher() ) ( self ))
+        let (err_type, call) = match init {
+            InitLog::Func {
+                err_type,
+                init,
+                associate: true,
+            } => (quote::quote!(#err_type), quote::quote!((#init)())),
+            InitLog::Func {
+                err_type,
+                init,
+                associate: false,
+            } => (
+                quote::quote!(#err_type),
+                quote::quote!(
+                    fn __fetcher()
+                    -> impl Fn(&#ident) -> ::core::result::Result<(), #err_type>{
+                        #init
+                    }
+                    ( __fetcher() ) ( self )),
+            ),
+            InitLog::Subscriber {
+                subscriber,
+                level_from,
+                format_from,
+            } => {
+                let default = SubscriberLogger::default();
+                let subscriber = subscriber.unwrap_or(&default);
+                // `None` provided keep the default
+                let directives = match (&subscriber.level, level_from) {
+                    (_, Some(provide)) => quote::quote! {
+                        if let ::core::option::Option::Some(directives) =
+                            ::axum_starter::LogSetting::into_setting(#provide)
+                        {
+                            logger = logger.directives(directives);
+                        }
+                    },
+                    (Some(level), None) => quote::quote!(logger = logger.directives(#level);),
+                    (None, None) => quote::quote!(),
+                };
+                let format = match (subscriber.format_variant(), format_from) {
+                    (_, Some(provide)) => quote::quote! {
+                        if let ::core::option::Option::Some(format) =
+                            ::axum_starter::LogSetting::into_setting(#provide)
+                        {
+                            logger = logger.format(
+                                <::axum_starter::LogFormat as ::core::str::FromStr>::from_str(&format)?,
+                            );
+                        }
+                    },
+                    (Some(variant), None) => {
+                        quote::quote!(logger = logger.format(::axum_starter::LogFormat::#variant);)
+                    }
+                    (None, None) => quote::quote!(),
+                };
+                (
+                    quote::quote!(::axum_starter::SubscriberError),
+                    quote::quote! {
+                        #[allow(unused_mut)]
+                        let mut logger = ::axum_starter::SubscriberLogger::new();
+                        #directives
+                        #format
+                        logger.init()
+                    },
+                )
+            }
         };
 
         let token = quote::quote! {
@@ -177,3 +277,158 @@ impl<'r> ToTokens for ImplServerEffect<'r> {
         tokens.extend(token);
     }
 }
+
+pub struct ImplCli<'r> {
+    ident: &'r syn::Ident,
+    fields: Vec<CliArg<'r>>,
+}
+
+struct CliArg<'r> {
+    field: &'r syn::Ident,
+    long: String,
+    help: String,
+    optional: bool,
+}
+
+/// the flags of `Cli` itself
+const RESERVED_LONG: [&str; 3] = ["config", "profile", "help"];
+
+impl<'r> ImplCli<'r> {
+    pub fn new(input: &'r DeriveInput, provided: &ProvidedFields) -> darling::Result<Option<Self>> {
+        if !input.cli {
+            return Ok(None);
+        }
+        let Some(fields) = input.data.as_ref().take_struct() else {
+            return Ok(None);
+        };
+        let fields = fields
+            .fields
+            .into_iter()
+            .filter(|field| {
+                !field.cli.skip
+                    && field
+                        .ident
+                        .as_ref()
+                        .is_some_and(|ident| provided.contains(ident))
+            })
+            .filter_map(|field @ ConfField { ident, ty, cli, .. }| {
+                let ident = ident.as_ref()?;
+                Some(CliArg {
+                    field: ident,
+                    long: cli
+                        .long
+                        .clone()
+                        .unwrap_or_else(|| ident.to_string().to_kebab_case()),
+                    help: field.doc(),
+                    optional: option_inner(ty).is_some(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // clap panic on the same flag at runtime
+        let mut errors = darling::Error::accumulator();
+        for (idx, arg) in fields.iter().enumerate() {
+            if RESERVED_LONG.contains(&arg.long.as_str()) {
+                errors.push(
+                    darling::Error::custom(format!(
+                        "`--{}` is used by `Cli`, rename it by `#[conf(cli(long = \"...\"))]`",
+                        arg.long
+                    ))
+                    .with_span(arg.field),
+                );
+            } else if fields[..idx].iter().any(|prev| prev.long == arg.long) {
+                errors.push(
+                    darling::Error::custom(format!("duplicate flag `--{}`", arg.long))
+                        .with_span(arg.field),
+                );
+            }
+        }
+        errors.finish()?;
+
+        Ok(Some(ImplCli {
+            ident: &input.ident,
+            fields,
+        }))
+    }
+
+    /// the arg id, namespaced from the ones of `Cli`
+    fn id(field: &syn::Ident) -> String {
+        format!("conf.{field}")
+    }
+}
+
+impl<'r> ToTokens for ImplCli<'r> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let ImplCli { ident, fields } = self;
+
+        let args = fields.iter().map(
+            |CliArg {
+                 field, long, help, ..
+             }| {
+                let id = Self::id(field);
+                let value_name = field.to_string().to_shouty_snake_case();
+                quote::quote! {
+                    .arg(
+                        ::axum_starter::clap::Arg::new(#id)
+                            .long(#long)
+                            .value_name(#value_name)
+                            .global(true)
+                            .help(#help)
+                    )
+                }
+            },
+        );
+
+        let updates = fields.iter().map(
+            |CliArg {
+                 field,
+                 long,
+                 optional,
+                 ..
+             }| {
+                let id = Self::id(field);
+                let parsed = quote::quote! {
+                    ::core::str::FromStr::from_str(value).map_err(|err| {
+                        ::axum_starter::clap::Error::raw(
+                            ::axum_starter::clap::error::ErrorKind::ValueValidation,
+                            ::std::format!("invalid value for `--{}`: {}\n", #long, err),
+                        )
+                    })?
+                };
+                let parsed = if *optional {
+                    quote::quote!(::core::option::Option::Some(#parsed))
+                } else {
+                    parsed
+                };
+                quote::quote! {
+                    if let ::core::option::Option::Some(value) =
+                        matches.get_one::<::std::string::String>(#id)
+                    {
+                        self.#field = #parsed;
+                    }
+                }
+            },
+        );
+
+        let token = quote::quote! {
+            impl ::axum_starter::CliConfigure for #ident {
+                fn augment_args(
+                    command: ::axum_starter::clap::Command,
+                ) -> ::axum_starter::clap::Command {
+                    command
+                    #(#args)*
+                }
+
+                fn update_from_args(
+                    &mut self,
+                    matches: &::axum_starter::clap::ArgMatches,
+                ) -> ::core::result::Result<(), ::axum_starter::clap::Error> {
+                    #(#updates)*
+                    ::core::result::Result::Ok(())
+                }
+            }
+        };
+
+        tokens.extend(token)
+    }
+}
@@ -1,7 +1,19 @@
-use darling::util::Override;
-use syn::{Expr, Path, Type};
+use darling::{
+    ast::Data,
+    util::{Ignored, Override, SpannedValue},
+    FromDeriveInput,
+};
+use syn::{Attribute, Expr, ExprLit, Ident, Lit, Meta, MetaNameValue, Path, Type};
 
-use crate::utils::check_callable_expr;
+use proc_macro2::TokenStream;
+
+use crate::{
+    derive_provider::macro_models::{
+        derive_model::{ProviderDerive, ProviderNeeds},
+        fields::{FieldInfo, ProvideType},
+    },
+    utils::{check_callable_expr, option_inner, snake_to_upper},
+};
 
 #[derive(Debug, darling::FromDeriveInput)]
 #[darling(attributes(conf), supports(struct_named))]
@@ -12,7 +24,124 @@ pub struct DeriveInput {
     pub(super) logger: Option<Logger>,
     #[darling(default)]
     pub(super) server: Override<Path>,
+    #[darling(default)]
+    pub(super) cli: bool,
     pub(super) ident: syn::Ident,
+    pub(super) data: Data<Ignored, ConfField>,
+}
+
+impl DeriveInput {
+    /// the field named `name`
+    pub(super) fn field(&self, name: &Ident) -> darling::Result<&ConfField> {
+        self.data
+            .as_ref()
+            .take_struct()
+            .and_then(|fields| {
+                fields
+                    .fields
+                    .into_iter()
+                    .find(|field| field.ident.as_ref() == Some(name))
+            })
+            .ok_or_else(|| {
+                darling::Error::custom(format!("no field named `{name}`")).with_span(name)
+            })
+    }
+}
+
+/// the fields provided by the `Provider` derive, parsed by its model
+pub struct ProvidedFields(ProviderNeeds);
+
+impl ProvidedFields {
+    pub(super) fn parse(input: &syn::DeriveInput) -> darling::Result<Self> {
+        ProviderDerive::from_derive_input(input)
+            .map(ProviderDerive::into_needs)
+            .map(Self)
+    }
+
+    fn get(&self, name: &Ident) -> Option<&FieldInfo> {
+        self.0
+            .provide
+            .iter()
+            .find(|info| info.src_field_ident == *name)
+    }
+
+    /// whether the field is provided, that is not `#[provider(skip)]`
+    pub(super) fn contains(&self, name: &Ident) -> bool {
+        self.get(name).is_some()
+    }
+
+    /// the expr providing the value of field by `Provider`, the same as the `Provider` derive
+    pub(super) fn provide_field(&self, name: &Ident) -> darling::Result<TokenStream> {
+        let Some(info) = self.get(name) else {
+            return Err(
+                darling::Error::custom(format!("field `{name}` is not provided")).with_span(name),
+            );
+        };
+        if info.secret.is_some() {
+            return Err(darling::Error::custom(format!(
+                "field `{name}` with `secret` is not supported, the log setting is not a secret"
+            ))
+            .with_span(name));
+        }
+        let ty = match info.default {
+            Some(_) => option_inner(&info.ty).unwrap_or(&info.ty),
+            None => &info.ty,
+        };
+
+        Ok(match (&info.wrapper_name, info.provide_type) {
+            (None, ProvideType::Ref) => {
+                quote::quote!(::axum_starter::Provider::<&#ty>::provide(self))
+            }
+            (None, ProvideType::Owned) => {
+                quote::quote!(::axum_starter::Provider::<#ty>::provide(self))
+            }
+            (Some(wrap), ProvideType::Ref) => {
+                quote::quote!(::axum_starter::Provider::<#wrap<'_>>::provide(self).0)
+            }
+            (Some(wrap), ProvideType::Owned) => {
+                quote::quote!(::axum_starter::Provider::<#wrap>::provide(self).0)
+            }
+        })
+    }
+}
+
+#[derive(Debug, darling::FromField)]
+#[darling(attributes(conf), forward_attrs(doc))]
+pub struct ConfField {
+    pub(super) ident: Option<syn::Ident>,
+    pub(super) ty: Type,
+    pub(super) attrs: Vec<Attribute>,
+    #[darling(default)]
+    pub(super) cli: CliField,
+}
+
+#[derive(Debug, Default, darling::FromMeta)]
+pub struct CliField {
+    #[darling(default)]
+    pub(super) skip: bool,
+    #[darling(default)]
+    pub(super) long: Option<String>,
+}
+
+impl ConfField {
+    /// the doc comment of the field
+    pub(super) fn doc(&self) -> String {
+        self.attrs
+            .iter()
+            .filter_map(|attr| match &attr.meta {
+                Meta::NameValue(MetaNameValue {
+                    path,
+                    value:
+                        Expr::Lit(ExprLit {
+                            lit: Lit::Str(doc), ..
+                        }),
+                    ..
+                }) if path.is_ident("doc") => Some(doc.value().trim().to_owned()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
 }
 
 #[derive(Debug, darling::FromMeta)]
@@ -33,17 +162,72 @@ pub struct Provider {
 }
 
 #[derive(Debug, darling::FromMeta)]
-#[darling(and_then = "Self::check_expr")]
+#[darling(and_then = "Self::check")]
 pub struct Logger {
-    pub(super) func: Expr,
-    pub(super) error: Type,
+    #[darling(default)]
+    pub(super) func: Option<Expr>,
+    #[darling(default)]
+    pub(super) error: Option<Type>,
     #[darling(default)]
     pub(super) associate: bool,
+    #[darling(default)]
+    pub(super) tracing_subscriber: Option<Override<SubscriberLogger>>,
+}
+
+/// the built-in `SubscriberLogger`
+#[derive(Debug, Default, darling::FromMeta)]
+#[darling(and_then = "Self::check")]
+pub struct SubscriberLogger {
+    #[darling(default)]
+    pub(super) level: Option<String>,
+    #[darling(default)]
+    pub(super) level_from: Option<Ident>,
+    #[darling(default)]
+    pub(super) format: Option<SpannedValue<String>>,
+    #[darling(default)]
+    pub(super) format_from: Option<Ident>,
 }
 
 impl Logger {
-    fn check_expr(self) -> darling::Result<Self> {
-        check_callable_expr(&self.func)?;
+    fn check(self) -> darling::Result<Self> {
+        match (&self.func, &self.error, &self.tracing_subscriber) {
+            (Some(func), Some(_), None) => check_callable_expr(func)?,
+            (None, None, Some(_)) if !self.associate => {}
+            (_, _, Some(_)) => Err(darling::Error::custom(
+                "`tracing_subscriber` conflict with `func`, `error` and `associate`",
+            ))?,
+            (None, _, None) => Err(darling::Error::missing_field("func"))?,
+            (_, None, None) => Err(darling::Error::missing_field("error"))?,
+        }
         Ok(self)
     }
 }
+
+/// the variants of `axum_starter::LogFormat`
+const LOG_FORMATS: [&str; 4] = ["full", "compact", "pretty", "json"];
+
+impl SubscriberLogger {
+    fn check(self) -> darling::Result<Self> {
+        if self.level.is_some() && self.level_from.is_some() {
+            Err(darling::Error::custom("`level` conflict with `level_from`"))?;
+        }
+        if self.format.is_some() && self.format_from.is_some() {
+            Err(darling::Error::custom(
+                "`format` conflict with `format_from`",
+            ))?;
+        }
+        if let Some(format) = &self.format {
+            if !LOG_FORMATS.contains(&format.to_ascii_lowercase().as_str()) {
+                Err(darling::Error::unknown_value(format).with_span(&format.span()))?;
+            }
+        }
+        Ok(self)
+    }
+
+    /// the variant name of `axum_starter::LogFormat`
+    pub(super) fn format_variant(&self) -> Option<Ident> {
+        let format = self.format.as_ref()?.to_ascii_lowercase();
+        let variant = snake_to_upper(&format);
+        Some(Ident::new(&variant, self.format.as_ref()?.span()))
+    }
+}
@@ -1,7 +1,7 @@
 use darling::FromDeriveInput;
 use syn::DeriveInput;
 
-use self::code_gen::{ImplAddress, ImplInitLog, ImplServerEffect};
+use self::code_gen::{ImplAddress, ImplCli, ImplInitLog, ImplServerEffect};
 
 mod code_gen;
 pub mod derive_inputs;
@@ -13,17 +13,21 @@ pub fn provider_derive(derive_input: DeriveInput) -> darling::Result<proc_macro:
     }
 
     let config = <derive_inputs::DeriveInput as FromDeriveInput>::from_derive_input(&derive_input)?;
+    // the fields referred are provided as the `Provider` derive
+    let provided = derive_inputs::ProvidedFields::parse(&derive_input)?;
 
     let address = config
         .address
         .as_ref()
         .map(|address| ImplAddress::from((address, &config.ident)));
-    let logger = Option::<ImplInitLog>::from(&config);
+    let logger = ImplInitLog::new(&config, &provided)?;
     let server = ImplServerEffect::from(&config);
+    let cli = ImplCli::new(&config, &provided)?;
     Ok(quote::quote! {
         #address
         #logger
         #server
+        #cli
     }
     .into())
 }
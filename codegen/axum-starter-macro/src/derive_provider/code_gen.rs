@@ -1,6 +1,8 @@
-use darling::ToTokens;
+use darling::{util::Override, ToTokens};
 
-use syn::{Ident, Lifetime, Type};
+use syn::{Expr, Ident, Lifetime, Type};
+
+use crate::utils::option_inner;
 
 use super::macro_models::{
     fields::{FieldInfo, ProvideType},
@@ -13,6 +15,10 @@ pub struct CodeGen<'i> {
     field_name: &'i Ident,
     field_ty: &'i Type,
     wrap: Option<&'i Ident>,
+    secret: bool,
+    /// load the secret by `Secret::from_env`
+    secret_env: Option<&'i str>,
+    default: Option<&'i Override<Expr>>,
 }
 
 impl<'i> CodeGen<'i> {
@@ -30,12 +36,20 @@ impl<'i> CodeGen<'i> {
                 field_ty: &info.ty,
                 wrap: info.wrapper_name.as_ref(),
                 provide_type: info.provide_type,
+                secret: info.secret.is_some(),
+                secret_env: info
+                    .secret
+                    .as_ref()
+                    .and_then(|secret| secret.env.as_deref()),
+                default: info.default.as_ref(),
             }),
             info.mappers.iter().map(
                 |TypeMapper {
                      ty,
                      by,
                      lifetime_inner,
+                     fallible,
+                     is_async,
                      ..
                  }| MapToCodeGen {
                     provider,
@@ -44,16 +58,45 @@ impl<'i> CodeGen<'i> {
                     map_by: by,
                     life: lifetime_inner,
                     field_ty: &info.ty,
+                    mode: match (fallible, is_async) {
+                        (_, true) => MapMode::AsyncFallible,
+                        (true, false) => MapMode::Fallible,
+                        (false, false) => MapMode::Infallible,
+                    },
                 },
             ),
         )
     }
 }
 
+impl<'i> CodeGen<'i> {
+    /// the provided type, `None` if provide by reference or providing may fail
+    pub fn owned_provide_type(&self) -> Option<proc_macro2::TokenStream> {
+        match (self.wrap, self.provide_type) {
+            _ if self.secret_env.is_some() => None,
+            (_, ProvideType::Ref) => None,
+            (Some(wrap), ProvideType::Owned) => Some(quote::quote!(#wrap)),
+            (None, ProvideType::Owned) => Some(self.value_type()),
+        }
+    }
+
+    fn value_type(&self) -> proc_macro2::TokenStream {
+        // `default` field is checked as `Option<T>`
+        let field_ty = match self.default {
+            Some(_) => option_inner(self.field_ty).unwrap_or(self.field_ty),
+            None => self.field_ty,
+        };
+        quote::quote!(#field_ty)
+    }
+}
+
 impl<'i> ToTokens for CodeGen<'i> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-        let ty = self.field_ty;
+        let field_ty = self.field_ty;
+        let ty = &self.value_type();
         if let Some(wrap) = self.wrap {
+            // `Secret` debug output is redacted, it is safe for the wrapper to impl `Debug`
+            let derive = self.secret.then(|| quote::quote!(#[derive(Debug)]));
             let token = match self.provide_type {
                 ProvideType::Ref => {
                     quote::quote! {
@@ -62,6 +105,7 @@ impl<'i> ToTokens for CodeGen<'i> {
                 }
                 ProvideType::Owned => {
                     quote::quote! {
+                        #derive
                         pub struct #wrap (pub  #ty);
                     }
                 }
@@ -69,7 +113,6 @@ impl<'i> ToTokens for CodeGen<'i> {
             tokens.extend(token);
         }
 
-        let ty = self.field_ty;
         let provide_type = &match (self.wrap, self.provide_type) {
             (None, ProvideType::Ref) => quote::quote!(& 'r #ty),
             (None, ProvideType::Owned) => quote::quote!( #ty),
@@ -78,23 +121,53 @@ impl<'i> ToTokens for CodeGen<'i> {
         };
 
         let field_name = self.field_name;
-        let fetch = match (self.wrap, self.provide_type) {
-            (None, ProvideType::Ref) => quote::quote! {&self.#field_name},
-            (None, ProvideType::Owned) => {
-                quote::quote! { std::clone::Clone::clone(&self.#field_name) }
-            }
-            (Some(wrap), ProvideType::Ref) => quote::quote! {#wrap ( &self.#field_name )},
-            (Some(wrap), ProvideType::Owned) => {
-                quote::quote! {#wrap ( std::clone::Clone::clone(&self.#field_name) )}
-            }
+        let cloned = match self.default {
+            Some(Override::Explicit(default)) => quote::quote! {
+                std::clone::Clone::clone(&self.#field_name).unwrap_or_else(|| #default)
+            },
+            Some(Override::Inherit) => quote::quote! {
+                std::clone::Clone::clone(&self.#field_name).unwrap_or_default()
+            },
+            None => quote::quote! { std::clone::Clone::clone(&self.#field_name) },
+        };
+        let wrapped = |value: proc_macro2::TokenStream| match self.wrap {
+            Some(wrap) => quote::quote! {#wrap ( #value )},
+            None => value,
+        };
+        let fetch = match self.provide_type {
+            ProvideType::Ref => wrapped(quote::quote! {&self.#field_name}),
+            ProvideType::Owned => wrapped(cloned),
         };
-        let ty = self.field_ty;
         let bound = match self.provide_type {
             ProvideType::Ref => quote::quote!(),
-            ProvideType::Owned => quote::quote! {where #ty : std::clone::Clone },
+            ProvideType::Owned => quote::quote! {where #field_ty : std::clone::Clone },
         };
         let this = self.provider;
 
+        if let Some(env) = self.secret_env {
+            let loaded = wrapped(quote::quote!(secret));
+            // `{env}_FILE` or `{env}` take precedence over the field value
+            let token = quote::quote! {
+                impl<'r> ::axum_starter::TryProvider<'r, #provide_type> for #this #bound{
+                    type Error = ::axum_starter::ProvideError;
+
+                    fn try_provide(&'r self) -> ::core::result::Result<#provide_type, Self::Error>{
+                        match ::axum_starter::Secret::from_env(#env) {
+                            ::core::result::Result::Ok(secret) => ::core::result::Result::Ok(#loaded),
+                            ::core::result::Result::Err(::axum_starter::SecretLoadError::NotPresent(_)) => {
+                                ::core::result::Result::Ok(#fetch)
+                            }
+                            ::core::result::Result::Err(err) => {
+                                ::core::result::Result::Err(::axum_starter::ProvideError::new(err))
+                            }
+                        }
+                    }
+                }
+            };
+            tokens.extend(token);
+            return;
+        }
+
         let token = quote::quote! {
             impl<'r> ::axum_starter::Provider<'r, #provide_type> for #this #bound{
                 fn provide(&'r self) -> #provide_type{
@@ -114,6 +187,28 @@ pub struct MapToCodeGen<'i> {
     map_to: &'i Type,
     map_by: &'i syn::Expr,
     life: &'i Option<Lifetime>,
+    mode: MapMode,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum MapMode {
+    /// impl `Provider`
+    Infallible,
+    /// impl `TryProvider`
+    Fallible,
+    /// impl `AsyncTryProvider`
+    AsyncFallible,
+}
+
+impl<'i> MapToCodeGen<'i> {
+    /// the provided type, `None` if it need lifetime or providing may fail
+    pub fn owned_provide_type(&self) -> Option<proc_macro2::TokenStream> {
+        let map_to = self.map_to;
+        match (self.mode, self.life) {
+            (MapMode::Infallible, None) => Some(quote::quote!(#map_to)),
+            _ => None,
+        }
+    }
 }
 
 impl<'i> ToTokens for MapToCodeGen<'i> {
@@ -125,6 +220,7 @@ impl<'i> ToTokens for MapToCodeGen<'i> {
             map_by,
             life,
             field_ty,
+            mode,
         } = self;
 
         let lifetime = match life {
@@ -133,22 +229,97 @@ impl<'i> ToTokens for MapToCodeGen<'i> {
             }
             None => quote::quote!('r),
         };
-        let fetch = quote::quote! {
-            fn __fetcher() -> impl for<#life> Fn(& #life #field_ty) -> #map_to{
-                #map_by
+
+        let token = match mode {
+            MapMode::Infallible => {
+                let fetch = quote::quote! {
+                    fn __fetcher() -> impl for<#life> Fn(& #life #field_ty) -> #map_to{
+                        #map_by
+                    }
+
+                    (  __fetcher() ) ( &self.#field_name )
+                };
+                quote::quote! {
+                    impl<#lifetime> ::axum_starter::Provider<#lifetime, #map_to> for #provider {
+                        fn provide(&#lifetime self) -> #map_to{
+                            # fetch
+                        }
+                    }
+                }
+            }
+            MapMode::Fallible => {
+                let fetch = quote::quote! {
+                    fn __fetcher() -> impl for<#life> Fn(& #life #field_ty)
+                    -> ::core::result::Result<#map_to, ::axum_starter::ProvideError>{
+                        |value| (#map_by)(value).map_err(::axum_starter::ProvideError::new)
+                    }
+
+                    (  __fetcher() ) ( &self.#field_name )
+                };
+                quote::quote! {
+                    impl<#lifetime> ::axum_starter::TryProvider<#lifetime, #map_to> for #provider {
+                        type Error = ::axum_starter::ProvideError;
+
+                        fn try_provide(&#lifetime self)
+                        -> ::core::result::Result<#map_to, Self::Error>{
+                            # fetch
+                        }
+                    }
+                }
             }
+            MapMode::AsyncFallible => {
+                quote::quote! {
+                    impl<#lifetime> ::axum_starter::AsyncTryProvider<#lifetime, #map_to> for #provider {
+                        type Error = ::axum_starter::ProvideError;
+                        type Future = ::std::pin::Pin<
+                            ::std::boxed::Box<
+                                dyn ::core::future::Future<
+                                    Output = ::core::result::Result<#map_to, Self::Error>
+                                > + #lifetime,
+                            >,
+                        >;
 
-            (  __fetcher() ) ( &self.#field_name )
+                        fn try_provide_async(&#lifetime self) -> Self::Future{
+                            let fut = (#map_by)(&self.#field_name);
+                            ::std::boxed::Box::pin(async move {
+                                fut.await.map_err(::axum_starter::ProvideError::new)
+                            })
+                        }
+                    }
+                }
+            }
         };
 
+        tokens.extend(token)
+    }
+}
+
+/// impl `AnyProvider` with all owned provide types
+pub struct AnyProviderCodeGen<'i> {
+    pub provider: &'i Ident,
+    pub types: Vec<proc_macro2::TokenStream>,
+}
+
+impl<'i> ToTokens for AnyProviderCodeGen<'i> {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let Self { provider, types } = self;
         let token = quote::quote! {
-            impl<#lifetime> ::axum_starter::Provider<#lifetime, #map_to> for #provider {
-                fn provide(&#lifetime self) -> #map_to{
-                    # fetch
+            impl ::axum_starter::AnyProvider for #provider {
+                fn provide_any(
+                    &self,
+                    id: ::core::any::TypeId,
+                ) -> ::core::option::Option<::std::boxed::Box<dyn ::core::any::Any>> {
+                    #(
+                        if id == ::core::any::TypeId::of::<#types>() {
+                            return ::core::option::Option::Some(::std::boxed::Box::new(
+                                <Self as ::axum_starter::Provider<#types>>::provide(self),
+                            ));
+                        }
+                    )*
+                    ::core::option::Option::None
                 }
             }
         };
-
         tokens.extend(token)
     }
 }
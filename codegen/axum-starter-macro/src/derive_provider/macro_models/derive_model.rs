@@ -1,5 +1,5 @@
 use darling::util;
-use syn::Ident;
+use syn::{Ident, Type};
 
 use crate::derive_provider::code_gen::{CodeGen, MapToCodeGen};
 
@@ -13,6 +13,9 @@ pub struct ProviderDerive {
     transparent: bool,
     #[darling(default, rename = "r#ref")]
     reference: bool,
+    /// the types provided by hand-written `Provider` impls, also covered by `AnyProvider`
+    #[darling(default, multiple)]
+    optional: Vec<Type>,
     data: darling::ast::Data<util::Ignored, ProviderField>,
 }
 
@@ -20,6 +23,7 @@ impl ProviderDerive {
     pub fn into_needs(self) -> ProviderNeeds {
         ProviderNeeds {
             ident: self.ident,
+            optional: self.optional,
             provide: self
                 .data
                 .take_struct()
@@ -33,6 +37,7 @@ impl ProviderDerive {
 
 pub struct ProviderNeeds {
     pub ident: Ident,
+    pub optional: Vec<Type>,
     pub provide: Vec<FieldInfo>,
 }
 
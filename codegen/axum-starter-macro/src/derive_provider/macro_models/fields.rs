@@ -1,12 +1,25 @@
+use darling::util::Override;
 use quote::format_ident;
-use syn::Type;
+use syn::{Expr, Type, TypePath};
 
 use syn::Ident;
 
-use crate::utils::snake_to_upper;
+use crate::utils::{option_inner, snake_to_upper};
 
 use super::type_mapper::TypeMapper;
 
+/// `secret(env = "KEY")`
+#[derive(Debug, Default, darling::FromMeta)]
+pub struct SecretEnv {
+    pub env: Option<String>,
+}
+
+/// whether the type is `Secret<T>`
+fn is_secret(ty: &Type) -> bool {
+    matches!(ty, Type::Path(TypePath { qself: None, path })
+        if path.segments.last().is_some_and(|seg| seg.ident == "Secret"))
+}
+
 #[derive(Debug, darling::FromField)]
 #[darling(attributes(provider), and_then = "ProviderField::check_correct")]
 pub struct ProviderField {
@@ -27,6 +40,14 @@ pub struct ProviderField {
     #[darling(default)]
     ignore_global: bool,
 
+    /// the field is `Secret<T>`, optional loading from the environment
+    #[darling(default)]
+    secret: Option<Override<SecretEnv>>,
+
+    /// provide the `T` of `Option<T>` field, fallback to the default value
+    #[darling(default)]
+    default: Option<Override<Expr>>,
+
     #[darling(default)]
     rename: Option<Ident>,
 
@@ -45,6 +66,8 @@ impl ProviderField {
             map_to: aliases,
             provide_ref,
             ignore_global,
+            secret,
+            default,
         } = self;
 
         match &ident {
@@ -58,10 +81,60 @@ impl ProviderField {
             }
         }
 
-        if skip && (transparent || rename.is_some() || !aliases.is_empty() || provide_ref) {
+        if skip
+            && (transparent
+                || rename.is_some()
+                || !aliases.is_empty()
+                || provide_ref
+                || secret.is_some()
+                || default.is_some())
+        {
             Err(darling::Error::duplicate_field("skip").with_span(&skip))?;
         }
 
+        if let Some(secret) = &secret {
+            if provide_ref {
+                Err(
+                    darling::Error::custom("`secret` can not be provided by `ref`")
+                        .with_span(&provide_ref),
+                )?;
+            }
+            // thus the `Debug` of the config is redacted, and the value is zeroized on drop
+            let value_ty = match default {
+                Some(_) => option_inner(&ty).unwrap_or(&ty),
+                None => &ty,
+            };
+            if !is_secret(value_ty) {
+                Err(darling::Error::custom(
+                    "`secret` require the field to be `Secret<T>` (or `Option<Secret<T>>` with `default`)",
+                )
+                .with_span(&ty))?;
+            }
+            if let Override::Explicit(SecretEnv { env: Some(_) }) = secret {
+                if default.is_some() {
+                    Err(
+                        darling::Error::custom("`secret(env)` can not be used with `default`")
+                            .with_span(&ty),
+                    )?;
+                }
+            }
+        }
+
+        if default.is_some() {
+            if option_inner(&ty).is_none() {
+                Err(
+                    darling::Error::custom("`default` require the field to be `Option<T>`")
+                        .with_span(&ty),
+                )?;
+            }
+            if provide_ref {
+                Err(
+                    darling::Error::custom("`default` can not be provided by `ref`")
+                        .with_span(&provide_ref),
+                )?;
+            }
+        }
+
         Ok(Self {
             ident,
             ty,
@@ -71,6 +144,8 @@ impl ProviderField {
             map_to: aliases,
             provide_ref,
             ignore_global,
+            secret,
+            default,
         })
     }
 
@@ -84,11 +159,16 @@ impl ProviderField {
             map_to,
             provide_ref,
             ignore_global,
+            secret,
+            default,
         } = self;
         let ident = ident?;
         let upper_ident = format_ident!("{}", snake_to_upper(&ident.to_string()));
         let transparent = transparent || (outer_transparent && !ignore_global);
-        let provide_ref = provide_ref || (outer_ref && !ignore_global);
+        // secret and default always provide the owned value
+        let provide_ref =
+            secret.is_none() && default.is_none() && (provide_ref || (outer_ref && !ignore_global));
+        let secret = secret.map(|secret| secret.unwrap_or_default());
         if skip {
             None
         } else {
@@ -102,6 +182,8 @@ impl ProviderField {
                     None
                 },
                 mappers: map_to,
+                secret,
+                default,
                 provide_type: if !provide_ref {
                     ProvideType::Owned
                 } else {
@@ -119,6 +201,9 @@ pub struct FieldInfo {
     pub provide_type: ProvideType,
     pub wrapper_name: Option<Ident>,
     pub mappers: Vec<TypeMapper>,
+    /// [Some] if the field is `Secret<T>`
+    pub secret: Option<SecretEnv>,
+    pub default: Option<Override<Expr>>,
 }
 
 #[derive(Debug, Clone, Copy)]
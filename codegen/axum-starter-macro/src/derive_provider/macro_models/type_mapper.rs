@@ -9,6 +9,12 @@ pub struct TypeMapper {
     pub ty: Type,
     pub by: Expr,
     pub lifetime: Option<String>,
+    /// the `by` function return `Result<ty, E>`
+    #[darling(default)]
+    pub fallible: bool,
+    /// the `by` function return `Future<Output = Result<ty, E>>`
+    #[darling(default, rename = "r#async")]
+    pub is_async: bool,
     #[darling(skip, default)]
     pub lifetime_inner: Option<Lifetime>,
 }
@@ -1,10 +1,13 @@
 use darling::FromDeriveInput;
 use syn::DeriveInput;
 
-use self::macro_models::derive_model::ProviderDerive;
+use self::{
+    code_gen::{AnyProviderCodeGen, CodeGen, MapToCodeGen},
+    macro_models::derive_model::ProviderDerive,
+};
 
 mod code_gen;
-mod macro_models;
+pub(crate) mod macro_models;
 
 pub fn provider_derive(derive_input: DeriveInput) -> darling::Result<proc_macro::TokenStream> {
     if !derive_input.generics.params.is_empty() {
@@ -16,10 +19,20 @@ pub fn provider_derive(derive_input: DeriveInput) -> darling::Result<proc_macro:
         <ProviderDerive as FromDeriveInput>::from_derive_input(&derive_input)?.into_needs();
 
     let (code_gen, map_gen) = provider.to_code_gens();
+    let any_provider = AnyProviderCodeGen {
+        provider: &provider.ident,
+        types: code_gen
+            .iter()
+            .filter_map(CodeGen::owned_provide_type)
+            .chain(map_gen.iter().filter_map(MapToCodeGen::owned_provide_type))
+            .chain(provider.optional.iter().map(|ty| quote::quote!(#ty)))
+            .collect(),
+    };
 
     Ok(quote::quote! {
         #(#code_gen)*
         #(#map_gen)*
+        #any_provider
     }
     .into())
 }
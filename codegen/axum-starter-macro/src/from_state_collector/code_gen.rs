@@ -1,30 +1,132 @@
 use darling::ToTokens;
 use proc_macro2::Ident;
-use syn::{punctuated::Punctuated, Token, Type};
+use quote::format_ident;
+use syn::{punctuated::Punctuated, Generics, Token, Type, WherePredicate};
+
+use super::input::StateField;
 
 pub struct GenImplFromState<'s> {
     pub ident: &'s Ident,
-    pub fields: Vec<(Option<Ident>, Type)>,
+    pub generics: &'s Generics,
+    pub from_ref: bool,
+    pub fields: Vec<StateField>,
+}
+
+impl<'s> GenImplFromState<'s> {
+    /// the field type is a bare type param, like `T`
+    fn is_type_param(&self, ty: &Type) -> bool {
+        match ty {
+            Type::Path(path) if path.qself.is_none() => path
+                .path
+                .get_ident()
+                .map(|ident| {
+                    self.generics
+                        .type_params()
+                        .any(|param| &param.ident == ident)
+                })
+                .unwrap_or_default(),
+            _ => false,
+        }
+    }
+
+    /// the generics with extra where predicates
+    fn generics_with<I>(&self, predicates: I) -> Generics
+    where
+        I: IntoIterator<Item = WherePredicate>,
+    {
+        let mut generics = self.generics.clone();
+        if self.generics.params.is_empty() {
+            return generics;
+        }
+        generics.make_where_clause().predicates.extend(predicates);
+        generics
+    }
 }
 
 impl<'s> ToTokens for GenImplFromState<'s> {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-        let GenImplFromState { ident, fields } = self;
+        let GenImplFromState { ident, fields, .. } = self;
+        let (_, ty_generics, _) = self.generics.split_for_impl();
+
+        let locals = (0..fields.len())
+            .map(|idx| format_ident!("__state_{idx}"))
+            .collect::<Vec<_>>();
+
+        // shared fields are fetched first, thus a following `take` of the same type
+        // will not remove it before
+        let (shared, owned): (Vec<_>, Vec<_>) = fields
+            .iter()
+            .zip(&locals)
+            .partition(|(field, _)| field.shared);
+        let fetches = shared.into_iter().chain(owned).map(
+            |(
+                StateField {
+                    ty,
+                    shared,
+                    key,
+                    nested,
+                    ..
+                },
+                local,
+            )| match (shared, key) {
+                _ if *nested => quote::quote!(
+                    let #local = <#ty as ::axum_starter::FromStateCollector>::fetch_mut(collector)?;
+                ),
+                (true, None) => quote::quote!(let #local = collector.get::<#ty>()?;),
+                (true, Some(key)) => quote::quote!(let #local = collector.get_keyed::<#ty>(#key)?;),
+                (false, None) => quote::quote!(let #local = collector.take::<#ty>()?;),
+                (false, Some(key)) => {
+                    quote::quote!(let #local = collector.take_keyed::<#ty>(#key)?;)
+                }
+            },
+        );
+
+        let from_refs = fields
+            .iter()
+            .enumerate()
+            // the orphan rule reject `impl<T> FromRef<State<T>> for T`
+            .filter(|(_, field)| {
+                self.from_ref && field.gen_from_ref() && !self.is_type_param(&field.ty)
+            })
+            .map(
+                |(
+                    idx,
+                    StateField {
+                        ident: field, ty, ..
+                    },
+                )| {
+                    let access = match field {
+                        Some(field) => quote::quote!(#field),
+                        None => syn::Index::from(idx).into_token_stream(),
+                    };
+                    let generics =
+                        self.generics_with([syn::parse_quote!(#ty: ::core::clone::Clone)]);
+                    let (impl_generics, _, where_clause) = generics.split_for_impl();
+                    quote::quote! {
+                        impl #impl_generics ::axum_starter::FromRef<#ident #ty_generics> for #ty #where_clause {
+                            fn from_ref(input: &#ident #ty_generics) -> Self {
+                                ::core::clone::Clone::clone(&input.#access)
+                            }
+                        }
+                    }
+                },
+            );
 
         let field_gen = fields
             .iter()
-            .map(|(field, ty)| {
-                if let Some(field) = field {
-                    quote::quote!(#field : collector.take::<#ty>()? )
+            .zip(&locals)
+            .map(|(StateField { ident, .. }, local)| {
+                if let Some(field) = ident {
+                    quote::quote!(#field : #local)
                 } else {
-                    quote::quote!(collector.take::<#ty>()?)
+                    quote::quote!(#local)
                 }
             })
             .collect::<Punctuated<_, Token!(,)>>();
 
         let self_construct = if fields.is_empty() {
             quote::quote!(#ident)
-        } else if fields.iter().all(|(f, _)| f.is_none()) {
+        } else if fields.iter().all(|f| f.ident.is_none()) {
             quote::quote!(
                 #ident (
                     #field_gen
@@ -38,16 +140,60 @@ impl<'s> ToTokens for GenImplFromState<'s> {
             )
         };
 
+        // keyed state is not tracked by type
+        let required = fields
+            .iter()
+            .filter(|field| field.key.is_none())
+            .rev()
+            .fold(
+                quote::quote!(::axum_starter::StateNil),
+                |tail, StateField { ty, nested, .. }| {
+                    if *nested {
+                        quote::quote!(
+                            <<#ty as ::axum_starter::RequireState>::Required as ::axum_starter::StateList>::Append<#tail>
+                        )
+                    } else {
+                        quote::quote!(::axum_starter::StateCons<#ty, #tail>)
+                    }
+                },
+            );
+
+        let fetch_generics = self.generics_with(fields.iter().map(
+            |StateField {
+                 ty, shared, nested, ..
+             }| match (nested, shared) {
+                (true, _) => syn::parse_quote!(#ty: ::axum_starter::FromStateCollector),
+                (false, true) => syn::parse_quote!(#ty: ::core::clone::Clone + 'static),
+                (false, false) => syn::parse_quote!(#ty: 'static),
+            },
+        ));
+        let (fetch_impl, _, fetch_where) = fetch_generics.split_for_impl();
+
+        let require_generics = self.generics_with(
+            fields
+                .iter()
+                .filter(|field| field.nested)
+                .map(|StateField { ty, .. }| syn::parse_quote!(#ty: ::axum_starter::RequireState)),
+        );
+        let (require_impl, _, require_where) = require_generics.split_for_impl();
+
         let token = quote::quote! {
-            impl ::axum_starter::FromStateCollector for #ident {
+            impl #fetch_impl ::axum_starter::FromStateCollector for #ident #ty_generics #fetch_where {
                 fn fetch_mut(
                     collector: &mut ::axum_starter::StateCollector,
                 ) -> core::result::Result<Self, ::axum_starter::TypeNotInState> {
+                    #(#fetches)*
                     core::result::Result::Ok(
                         #self_construct
                     )
                 }
             }
+
+            impl #require_impl ::axum_starter::RequireState for #ident #ty_generics #require_where {
+                type Required = #required;
+            }
+
+            #(#from_refs)*
         };
 
         tokens.extend(token)
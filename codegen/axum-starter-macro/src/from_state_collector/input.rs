@@ -2,14 +2,38 @@ use darling::{ast::Data, util::Ignored};
 use syn::Type;
 
 #[derive(Debug, darling::FromDeriveInput)]
-#[darling(supports(struct_named, struct_tuple, struct_unit))]
+#[darling(attributes(state), supports(struct_named, struct_tuple, struct_unit))]
 pub struct StateInput {
     pub ident: syn::Ident,
+    /// generate `FromRef` for the fields
+    #[darling(default)]
+    pub from_ref: bool,
+    pub generics: syn::Generics,
     pub data: Data<Ignored, StateField>,
 }
 
 #[derive(Debug, darling::FromField)]
+#[darling(attributes(state))]
 pub struct StateField {
     pub ident: Option<syn::Ident>,
     pub ty: Type,
+    /// fetch a clone of the state, keep the state in collector
+    #[darling(default)]
+    pub shared: bool,
+    /// fetch the state inserted with the key
+    #[darling(default)]
+    pub key: Option<String>,
+    /// fetch the sub state by its `FromStateCollector`
+    #[darling(default)]
+    pub nested: bool,
+    /// not generate `FromRef` for this field, with `from_ref` on container
+    #[darling(default)]
+    pub skip_from_ref: bool,
+}
+
+impl StateField {
+    /// keyed field type may be duplicate, thus not generate `FromRef`
+    pub fn gen_from_ref(&self) -> bool {
+        !self.skip_from_ref && self.key.is_none()
+    }
 }
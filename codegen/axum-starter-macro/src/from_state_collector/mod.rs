@@ -1,34 +1,58 @@
-use darling::FromDeriveInput;
-use syn::{spanned::Spanned, DeriveInput};
+use darling::{FromDeriveInput, ToTokens};
+use syn::DeriveInput;
 
-use self::{
-    code_gen::GenImplFromState,
-    input::{StateField, StateInput},
-};
+use self::{code_gen::GenImplFromState, input::StateInput};
 
 pub mod code_gen;
 pub mod input;
 
 pub fn from_state_collector_macro(input: DeriveInput) -> darling::Result<proc_macro::TokenStream> {
-    if !input.generics.params.is_empty() {
-        Err(
-            darling::error::Error::unexpected_type("Not support generic type")
-                .with_span(&input.generics.span()),
-        )?;
-    }
-
     let input: StateInput = FromDeriveInput::from_derive_input(&input)?;
 
+    let fields = input
+        .data
+        .take_struct()
+        .ok_or_else(|| syn::Error::new(input.ident.span(), "Expect Struct, but get Enum"))?
+        .fields;
+
+    let mut errors = darling::Error::accumulator();
+    for field in fields
+        .iter()
+        .filter(|f| f.nested && (f.shared || f.key.is_some()))
+    {
+        errors.push(
+            darling::Error::custom("`nested` can not be used with `shared` or `key`")
+                .with_span(&field.ty),
+        );
+    }
+    if input.from_ref {
+        // the same type can not `FromRef` from two fields
+        let from_ref_fields = fields
+            .iter()
+            .filter(|f| f.gen_from_ref())
+            .collect::<Vec<_>>();
+        for (idx, field) in from_ref_fields.iter().enumerate() {
+            let ty = field.ty.to_token_stream().to_string();
+            if from_ref_fields[..idx]
+                .iter()
+                .any(|prev| prev.ty.to_token_stream().to_string() == ty)
+            {
+                errors.push(
+                    darling::Error::custom(
+                        "duplicate field type can not generate `FromRef`, using `#[state(skip_from_ref)]` on one of them",
+                    )
+                    .with_span(&field.ty),
+                );
+            }
+        }
+    }
+    errors.finish()?;
+
     let code_gen = GenImplFromState {
         ident: &input.ident,
-        fields: input
-            .data
-            .take_struct()
-            .ok_or_else(|| syn::Error::new(input.ident.span(), "Expect Struct, but get Enum"))?
-            .fields
-            .into_iter()
-            .map(|StateField { ident, ty }| (ident, ty))
-            .collect(),
+        generics: &input.generics,
+        from_ref: input.from_ref,
+        fields,
     };
 
     Ok(quote::quote!(#code_gen).into())
@@ -16,7 +16,7 @@ mod utils;
 ///
 /// ```rust
 /// use std::net::SocketAddr;
-/// use axum_starter;
+/// use axum_starter::{self, Secret};
 /// use axum_starter_macro::Provider;
 /// #[derive(Debug, Provider)]
 /// #[provider(r#ref)]
@@ -33,10 +33,20 @@ mod utils;
 ///     // `ignore_global` will ignore the `ref` on the container
 ///     #[provider(ignore_global)]
 ///     foo_bar: (i32, i32),
+///     // this will impl `Provide<ApiKey>`
+///     // where `ApiKey` is `struct ApiKey(Secret<String>);`
+///     // loaded from env `API_KEY` or the file in `API_KEY_FILE` if present
+///     #[provider(secret(env = "API_KEY"))]
+///     api_key: Secret<String>,
 /// }
 ///
-/// fn foo_fetch(foo: &String, FooBar(foo_bar): FooBar){
-///     // do somethings
+/// fn foo_fetch(foo: &String, FooBar(foo_bar): FooBar){}
+///
+/// // fetching `ApiKey` may fail, thus a fallible prepare
+/// fn key_fetch(ApiKey(key): ApiKey){
+///     // `Debug` output of secret is redacted
+///     println!("{key:?}");
+///     let _key: &String = key.expose_secret();
 /// }
 ///
 /// ```  
@@ -45,8 +55,31 @@ mod utils;
 /// - using `transparent` to impl `Provider` the original type instant of generate a wrapper type. Can be using on container to apply on all fields
 /// - using `ignore_global` to ignore the `ref` and `transparent` setting on container
 /// - using `skip` to not impl `Provider` for this field
+/// - using `secret` on a [`Secret<T>`](https://docs.rs/axum-starter/latest/axum_starter/struct.Secret.html) field
+///   (or `Option<Secret<T>>` with `default`), thus the `Debug` of the config is redacted and the value zeroized on drop.
+///   `secret` can not be used with `r#ref`
+///     - adding `env = "KEY"` to load from env `KEY` (or the file in `KEY_FILE`) first, fallback to the field,
+///       then impl `TryProvider` instead of `Provider`. Can not be used with `default`
+/// - using `default = "..."` on `Option<T>` field to provide `T`, the expr is used when the field is `None`.
+///   Just using `default` to fallback with [Default::default]. `default` can not be used with `r#ref`
 /// - using `map_to(ty , by)` to adding extra provide for [Type](syn::Type) by the giving function, if the type need lifetime mark,
-/// adding `lifetime = "'a"`, then using`'a` in your type for example `& 'a str`
+///   adding `lifetime = "'a"`, then using`'a` in your type for example `& 'a str`
+///     - adding `fallible` if the function return `Result<ty, E>`, then impl `TryProvider` instead of `Provider`
+///     - adding `r#async` if the function return `Future<Output = Result<ty, E>>`, then impl `AsyncTryProvider`
+/// - using `optional = "Type"` on container to let the `AnyProvider` also provide the `Type` by a hand-written `Provider` impl,
+///   otherwise the `#[optional]` argument of that type is always [None]. Can be used multiply times
+///
+/// a `secret` field must be declared as `Secret<T>`, so it never appears in the `Debug` output of the config
+///
+/// ```rust,compile_fail
+/// use axum_starter::Provider;
+///
+/// #[derive(Debug, Provider)]
+/// struct Configure {
+///     #[provider(secret)]
+///     api_key: String,
+/// }
+/// ```
 #[proc_macro_derive(Provider, attributes(provider))]
 pub fn derive_config_provider(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
@@ -63,7 +96,7 @@ pub fn derive_config_provider(input: proc_macro::TokenStream) -> proc_macro::Tok
 /// #[derive(Debug, Provider, Configure)]
 /// #[conf(
 ///     address(provide),
-///     logger(error = "log::SetLoggerError", func = "Self::init_log"),
+///     logger(error = "log::SetLoggerError", func = "Configure::init_log"),
 ///     server
 ///)]
 /// struct Configure {
@@ -83,22 +116,69 @@ pub fn derive_config_provider(input: proc_macro::TokenStream) -> proc_macro::Tok
 /// ### address
 /// - using `address(provide)` direct using the config provide to get address,
 /// - using `address(provide(ty = "..."))` similar to previous one, but using the provide type
-///     **Note**: the provided type need impl [Into<std::net::SocketAddr>](Into<std::net::SocketAddr>)
+///   **Note**: the provided type need impl [Into<std::net::SocketAddr>](Into<std::net::SocketAddr>)
 ///
 /// - using `address(func(path = "...", ty = "...", associate))` using provide function get the socket address
 ///     - `path` a path to a function or a closure expr, its signature is `Fn(config: &Self) -> $ty`
 ///     - `ty` (optional) default is [std::net::SocketAddr]
 ///     - `associate`(optional) set whether the function to call need argument `Self`,
-///        if set `associate` the signature of function to call is `Fn()->$ty`
+///       if set `associate` the signature of function to call is `Fn()->$ty`
 ///
 /// ### logger
 /// - using `logger(error="...", func="...",associate)` to impl `LoggerInitialization`,
-/// the `func` and `associate` is similar to the `path` and `associate` of `address(func(path="...", associate))` but the return type became `Result<(),$error>`
+///   the `func` and `associate` is similar to the `path` and `associate` of `address(func(path="...", associate))` but the return type became `Result<(),$error>`
 ///     - `error` the error that might occur during initialization the log system
+/// - using `logger(tracing_subscriber)` or `logger(tracing_subscriber(...))` to init the built-in `SubscriberLogger` (require `subscriber` feature),
+///   the filter directives can be changed at runtime by `LogReloadHandle`
+///     - `level="..."` or `level_from="field"` the filter directives, like `info,sqlx=warn`, `info` by default
+///     - `format="..."` or `format_from="field"` one of `full`, `compact`, `pretty` or `json`, `full` by default
+///     - the `field` is read by the `Provider` of the config, the provided value need impl `LogSetting`,
+///       like `String`, `&str` or `Option` of them, [None] keep the default.
+///       The field can not be `skip` or `secret`
+///
+/// ```rust
+/// use axum_starter::{Configure, Provider};
+///
+/// #[derive(Debug, Provider, Configure)]
+/// #[conf(logger(tracing_subscriber(format = "json", level_from = "log_level")))]
+/// struct Configure {
+///     #[provider(default = "String::from(\"info\")")]
+///     log_level: Option<String>,
+/// }
+/// ```
+///
+/// ```rust,compile_fail
+/// use axum_starter::{Configure, Provider, Secret};
+///
+/// #[derive(Debug, Provider, Configure)]
+/// #[conf(logger(tracing_subscriber(level_from = "log_level")))]
+/// struct Configure {
+///     #[provider(secret)]
+///     log_level: Secret<String>,
+/// }
+/// ```
 ///
 /// ### server
 /// - using `server="..."` to impl `ConfigureServerEffect` with internally call the `provide` func or
-/// just using `server` or ignore it to having an empty implement. The function look like `fn (&self, Builder<AddrIncome>) -> Builder<AddrIncome>`
+///   just using `server` or ignore it to having an empty implement. The function look like `fn (&self, Builder<AddrIncome>) -> Builder<AddrIncome>`
+///
+/// ### cli
+/// - using `cli` to impl `CliConfigure` (require `cli` feature), each field became a `--kebab-case` flag
+///   with its doc comment as help, the field type need impl [FromStr](std::str::FromStr) or be `Option<T: FromStr>`
+///     - field with `#[provider(skip)]` or `#[conf(cli(skip))]` will not have the flag
+///     - using `#[conf(cli(long = "..."))]` on field to rename the flag
+///     - `--config`, `--profile` and `--help` are used by `Cli` itself, the field of the same flag need to be renamed
+///
+/// ```rust,compile_fail
+/// use axum_starter::{Configure, Provider};
+///
+/// #[derive(Debug, Provider, Configure)]
+/// #[conf(cli)]
+/// struct Conf {
+///     // rename by `#[conf(cli(long = "conf-profile"))]`
+///     profile: String,
+/// }
+/// ```
 ///
 #[proc_macro_derive(Configure, attributes(conf))]
 pub fn derive_config_impl(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
@@ -109,7 +189,106 @@ pub fn derive_config_impl(input: proc_macro::TokenStream) -> proc_macro::TokenSt
 /// impl `FromStateCollector` for special type
 ///
 /// this implement is easy but boring, thus need macro to simplify it
-#[proc_macro_derive(FromStateCollector)]
+///
+/// each field is taken from the `StateCollector` by default, using `#[state(shared)]`
+/// to fetch a clone of the state and keep it in the collector, thus other
+/// `FromStateCollector` can also fetch it. The shared field type need impl [Clone]
+///
+/// using `#[state(key = "...")]` to fetch the state inserted with the key, for example by `AddKeyedState`,
+/// thus multiply fields can have the same type
+///
+/// using `#[state(from_ref)]` on container to also generate `axum::extract::FromRef` of each field,
+/// thus handlers can extract `State<Field>` directly. The field type need impl [Clone].
+/// Not using it together with `#[derive(FromRef)]`, which generate the same impls
+/// - using `#[state(skip_from_ref)]` to not generate it, for instance the type has implemented it
+/// - the keyed field is skipped, since the type may be duplicate
+/// - the non-keyed fields of the same type are rejected, skip all but one of them
+///
+/// using `#[state(nested)]` to fetch a sub state which also derive `FromStateCollector`,
+/// the `FromRef` of the sub state is generated with `from_ref`, but its fields can only be extracted from the sub state
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use axum::extract::FromRef;
+/// use axum_starter::FromStateCollector;
+///
+/// struct Pool;
+///
+/// #[derive(Clone, FromStateCollector)]
+/// struct Cache {
+///     capacity: usize,
+/// }
+///
+/// #[derive(Clone, FromStateCollector)]
+/// #[state(from_ref)]
+/// struct AppState {
+///     #[state(shared)]
+///     pool: Arc<Pool>,
+///     #[state(key = "primary")]
+///     primary: Arc<Pool>,
+///     #[state(shared, key = "replica")]
+///     replica: Arc<Pool>,
+///     #[state(nested)]
+///     cache: Cache,
+///     #[state(skip_from_ref)]
+///     name: String,
+/// }
+///
+/// fn extractable<T: FromRef<AppState>>() {}
+///
+/// extractable::<Arc<Pool>>();
+/// extractable::<Cache>();
+/// ```
+///
+/// the generic struct is also supported, the `FromRef` of the field whose type is a bare type param,
+/// like `T`, is not generated due to the orphan rule
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use axum::extract::FromRef;
+/// use axum_starter::FromStateCollector;
+///
+/// #[derive(Clone, FromStateCollector)]
+/// #[state(from_ref)]
+/// struct AppState<P: Send + Sync, C> {
+///     pool: Arc<P>,
+///     client: C,
+/// }
+///
+/// fn extractable<T: FromRef<AppState<String, u8>>>() {}
+///
+/// fn from_state<T: FromStateCollector>() {}
+///
+/// extractable::<Arc<String>>();
+/// from_state::<AppState<String, u8>>();
+/// ```
+///
+/// without `from_ref`, it works with `#[derive(FromRef)]` as before
+///
+/// ```rust
+/// use axum::extract::FromRef;
+/// use axum_starter::FromStateCollector;
+///
+/// #[derive(Clone, FromRef, FromStateCollector)]
+/// struct AppState {
+///     name: String,
+///     count: u32,
+/// }
+/// ```
+///
+/// ```rust,compile_fail
+/// use axum_starter::FromStateCollector;
+///
+/// #[derive(Clone, FromStateCollector)]
+/// #[state(from_ref)]
+/// struct AppState {
+///     #[state(shared)]
+///     name: String,
+///     #[state(shared)]
+///     alias: String,
+/// }
+/// ```
+#[proc_macro_derive(FromStateCollector, attributes(state))]
 pub fn derive_from_state_collector(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
     darling_err!(from_state_collector::from_state_collector_macro(
@@ -131,8 +310,8 @@ pub fn derive_from_state_collector(input: proc_macro::TokenStream) -> proc_macro
 /// - `Result<impl @, CustomError>`
 /// - `impl @`
 /// > the `@` can be `PrepareRouteEffect`,
-/// `PrepareStateEffect` or
-///  `PrepareMiddlewareEffect`
+/// > `PrepareStateEffect` or
+/// > `PrepareMiddlewareEffect`
 ///
 /// **Note** if the return type is `Result<impl @, Error>`, need add `?` following the
 /// generate Name
@@ -201,6 +380,67 @@ pub fn derive_from_state_collector(input: proc_macro::TokenStream) -> proc_macro
 /// }
 /// ```
 ///
+/// if the argument providing may fail, mark it with `#[fallible]` (using `TryProvider`) or
+/// `#[fallible(async)]` (using `AsyncTryProvider`, not support in `sync` prepare).
+/// The providing error will be a `PrepareArgError` tagged with the argument name
+///
+/// ```rust
+/// use axum_starter::{prepare, Provider};
+/// use std::net::SocketAddr;
+///
+/// #[derive(Provider)]
+/// struct Conf {
+///     #[provider(map_to(ty = "SocketAddr", by = "|s: &String| s.parse()", fallible))]
+///     upstream: String,
+///     #[provider(map_to(ty = "Vec<u8>", by = "load_key", r#async))]
+///     key_path: String,
+/// }
+///
+/// async fn load_key(path: &String) -> Result<Vec<u8>, std::io::Error> {
+///     // load the key from local vault file
+///     std::fs::read(path)
+/// }
+///
+/// #[prepare(Connect)]
+/// async fn prepare_upstream(#[fallible] addr: SocketAddr, #[fallible(async)] key: Vec<u8>) {
+///     // addr and key are ready
+/// }
+/// ```
+///
+/// if the argument may not be provided by the Config at all, mark the `Option<T>` argument with `#[optional]`,
+/// it will be [None] when the Config not provide `T`. This require the Config impl `AnyProvider`
+/// (generated by `Provider` derive) and `T` is owned and not a tuple.
+/// The `AnyProvider` only covers the types generated by the derive, the type provided by a hand-written
+/// `Provider` impl need to be listed by `#[provider(optional = "Type")]` on the Config
+///
+/// ```rust
+/// use axum_starter::{prepare, Provider};
+///
+/// #[derive(Provider)]
+/// struct Conf {
+///     #[provider(default = "8080")]
+///     port: Option<u16>,
+/// }
+///
+/// pub struct Tls;
+///
+/// #[prepare(Listen)]
+/// fn listen(Port(port): Port, #[optional] tls: Option<Tls>) {
+///     assert_eq!(port, 8080);
+///     // `Conf` not provide `Tls`
+///     assert!(tls.is_none());
+/// }
+/// ```
+///
+/// the tuple is rejected, as `AnyProvider` can not provide it
+///
+/// ```rust,compile_fail
+/// use axum_starter::prepare;
+///
+/// #[prepare(Listen)]
+/// fn listen(#[optional] pair: Option<(u16, String)>) {}
+/// ```
+///
 /// By default, the macro will not keep the origin function exist, if you want use that original function, using `origin`,
 /// the `origin` is after the `box` or `sync`, but before the Ident
 ///
@@ -4,9 +4,10 @@ use crate::prepare_macro::inputs::attr_name::PrepareFnMode;
 use crate::prepare_macro::DEFAULT_LIFETIME_SYMBOL;
 use quote::{format_ident, quote};
 use syn::spanned::Spanned;
-use syn::{Lifetime, Stmt, Type};
+use syn::{GenericArgument, Lifetime, PathArguments, Stmt, Type, TypePath};
 
-use super::inputs::input_fn::{ArgInfo, GenericWithBound, InputFn};
+use super::inputs::input_fn::{ArgInfo, ArgProvide, GenericWithBound, InputFn};
+use crate::utils::option_inner;
 
 pub struct CodeGen<'r> {
     may_fall: bool,
@@ -33,8 +34,20 @@ impl<'r> CodeGen<'r> {
             ret,
             fn_body,
         }: InputFn<'r>,
-    ) -> Self {
-        Self {
+    ) -> syn::Result<Self> {
+        if let (PrepareFnMode::Sync, Some(ArgInfo { patten, .. })) = (
+            prepare_mode,
+            args_type
+                .iter()
+                .find(|arg| arg.provide == ArgProvide::AsyncFallible),
+        ) {
+            Err(syn::Error::new(
+                patten.span(),
+                "`#[fallible(async)]` argument not support in `sync` prepare",
+            ))?;
+        }
+
+        Ok(Self {
             prepare_name,
             call_args: args_type,
             args_lifetime: arg_lifetime.as_ref(),
@@ -43,7 +56,7 @@ impl<'r> CodeGen<'r> {
             may_fall,
             ret_type: ret,
             fn_body,
-        }
+        })
     }
 }
 
@@ -80,15 +93,56 @@ impl<'r> ToTokens for CodeGen<'r> {
 
         let extra_bounds = prepare_generic.where_closure.as_ref();
 
-        let impl_bounds = call_args.iter().map(|ArgInfo { ty, .. }| {
+        let impl_bounds = call_args.iter().map(|ArgInfo { ty, provide, .. }| {
+            let provider = match provide {
+                ArgProvide::Provider => quote::quote!(Provider),
+                ArgProvide::Fallible => quote::quote!(TryProvider),
+                ArgProvide::AsyncFallible => quote::quote!(AsyncTryProvider),
+                ArgProvide::Optional => {
+                    return quote::quote! {
+                        Config: ::axum_starter::AnyProvider,
+                    }
+                }
+            };
             quote::quote! {
-                Config: for<#bound_lifetime> ::axum_starter::Provider<#bound_lifetime, #ty>,
+                Config: for<#bound_lifetime> ::axum_starter::#provider<#bound_lifetime, #ty>,
             }
         });
 
-        let args_fetch = call_args.iter().map(|_ty| {
+        let any_fallible = call_args.iter().any(|arg| arg.provide.is_fallible());
+
+        let args_fetch = call_args.iter().map(|ArgInfo { patten, ty, provide }| {
+            let provide = match provide {
+                ArgProvide::Provider => {
+                    return quote::quote! {
+                        ::axum_starter::Provider::provide(::core::ops::Deref::deref(&config))
+                    }
+                }
+                ArgProvide::Optional => {
+                    let inner = option_inner(ty);
+                    return quote::quote! {
+                        ::axum_starter::AnyProvider::provide_optional::<#inner>(
+                            ::core::ops::Deref::deref(&config)
+                        )
+                    };
+                }
+                ArgProvide::Fallible => quote::quote! {
+                    ::axum_starter::TryProvider::try_provide(::core::ops::Deref::deref(&config))
+                },
+                ArgProvide::AsyncFallible => quote::quote! {
+                    ::axum_starter::AsyncTryProvider::try_provide_async(
+                        ::core::ops::Deref::deref(&config)
+                    ).await
+                },
+            };
+            let arg_name = patten.to_token_stream().to_string();
             quote::quote! {
-                ::axum_starter::Provider::provide(::core::ops::Deref::deref(&config))
+                match #provide {
+                    ::core::result::Result::Ok(value) => value,
+                    ::core::result::Result::Err(err) => break '__prepare ::core::result::Result::Err(
+                        ::axum_starter::PrepareArgError::provide(#arg_name, err)
+                    ),
+                }
             }
         });
         let inner_struct_name = format_ident!("__InnerArgsStruct");
@@ -124,10 +178,55 @@ impl<'r> ToTokens for CodeGen<'r> {
             }
         };
 
-        let mapped_func_call = if *may_fall {
-            func_call
+        // `Result<T, E>` can be split syntactically, thus `T` can be `impl Trait`
+        let split_ret = ret_type.and_then(split_result);
+
+        // ret type
+        let ret_type = match ret_type {
+            Some(ty) => quote::quote!(#ty),
+            None => quote::quote!(()),
+        };
+
+        let prepare_body = if any_fallible {
+            let mapped = if *may_fall {
+                // evaluate the body in nested scope, thus `?` in the body
+                // still return the origin return type
+                let ret_type = match split_ret {
+                    Some((_, err)) => quote::quote!(::core::result::Result<_, #err>),
+                    None => ret_type.clone(),
+                };
+                let nested = match prepare_mode {
+                    PrepareFnMode::Sync => quote::quote!((move || -> #ret_type #func_call)()),
+                    _ => quote::quote!(async move {
+                        let ret: #ret_type = #func_call;
+                        ret
+                    }
+                    .await),
+                };
+                quote::quote!(
+                    let ret = #nested;
+                    ::axum_starter::IntoPrepareResult::into_prepare_result(ret)
+                )
+            } else {
+                quote::quote!(
+                    let ret = #func_call;
+                    ::core::result::Result::Ok(ret)
+                )
+            };
+            quote::quote!(
+                '__prepare: {
+                    #execute_prepare
+                    #mapped
+                }
+            )
+        } else if *may_fall {
+            quote::quote!(
+                #execute_prepare
+                #func_call
+            )
         } else {
             quote::quote!(
+                #execute_prepare
                 let ret = #func_call;
                 ::core::result::Result::Ok(
                     ret
@@ -140,35 +239,46 @@ impl<'r> ToTokens for CodeGen<'r> {
                 quote::quote! {
                     ::std::boxed::Box::pin(
                         async move {
-                            #execute_prepare
-                            #mapped_func_call
+                            #prepare_body
                         }
                     )
                 }
             }
             PrepareFnMode::Async => {
                 quote::quote! {
-                    #execute_prepare
-                    #mapped_func_call
+                    #prepare_body
                 }
             }
             PrepareFnMode::Sync => {
                 quote::quote! {
                     let ret = {
-                        #execute_prepare
-                        #mapped_func_call
+                        #prepare_body
                     };
                     ::axum_starter::ready(ret)
                 }
             }
         };
-        // ret type
-        let ret_type = match ret_type {
-            Some(ty) => quote::quote!(#ty),
-            None => quote::quote!(()),
-        };
-
-        let ret_type = if *may_fall {
+        let ret_type = if let (true, true, Some((ok, err))) = (any_fallible, *may_fall, split_ret) {
+            quote::quote!(
+                ::core::result::Result<#ok, ::axum_starter::PrepareArgError<#err>>
+            )
+        } else if any_fallible && *may_fall {
+            quote::quote!(
+                ::core::result::Result<
+                    <#ret_type as ::axum_starter::IntoPrepareResult>::Effect,
+                    ::axum_starter::PrepareArgError<
+                        <#ret_type as ::axum_starter::IntoPrepareResult>::Error
+                    >
+                >
+            )
+        } else if any_fallible {
+            quote::quote!(
+                ::core::result::Result<
+                    #ret_type ,
+                    ::axum_starter::PrepareArgError<::core::convert::Infallible>
+                >
+            )
+        } else if *may_fall {
             ret_type
         } else {
             quote::quote!(
@@ -225,3 +335,24 @@ impl<'r> ToTokens for CodeGen<'r> {
         tokens.extend(token)
     }
 }
+
+fn split_result(ty: &Type) -> Option<(&Type, &Type)> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let segment = path.segments.last()?;
+    if segment.ident != "Result" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    let mut types = args.args.iter().map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    match (types.next(), types.next(), types.next()) {
+        (Some(Some(ok)), Some(Some(err)), None) => Some((ok, err)),
+        _ => None,
+    }
+}
@@ -1,24 +1,104 @@
 use std::ops::Deref;
 
 use crate::prepare_macro::DEFAULT_LIFETIME_SYMBOL;
+use crate::utils::option_inner;
 use syn::visit_mut::VisitMut;
 use syn::{
-    punctuated::Punctuated, spanned::Spanned, visit_mut, ConstParam, FnArg, Generics, ItemFn,
-    Lifetime, LifetimeParam, Pat, PatType, PredicateType, Stmt, Token, Type, TypeParam,
-    TypeReference, WherePredicate,
+    punctuated::Punctuated, spanned::Spanned, visit_mut, Attribute, ConstParam, FnArg, Generics,
+    ItemFn, Lifetime, LifetimeParam, Meta, Pat, PatType, PredicateType, Stmt, Token, Type,
+    TypeParam, TypeReference, WherePredicate,
 };
 
+/// how the argument is provided by the Config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgProvide {
+    /// using `Provider`
+    Provider,
+    /// `#[fallible]`, using `TryProvider`
+    Fallible,
+    /// `#[fallible(async)]`, using `AsyncTryProvider`
+    AsyncFallible,
+    /// `#[optional]` on `Option<T>`, using `AnyProvider`
+    Optional,
+}
+
+impl ArgProvide {
+    const FALLIBLE: &'static str = "fallible";
+    const OPTIONAL: &'static str = "optional";
+
+    fn is_arg_attr(attr: &Attribute) -> bool {
+        attr.path().is_ident(Self::FALLIBLE) || attr.path().is_ident(Self::OPTIONAL)
+    }
+
+    fn from_arg(PatType { attrs, ty, .. }: &PatType) -> syn::Result<Self> {
+        let mut provide = Self::Provider;
+        for attr in attrs.iter().filter(|attr| Self::is_arg_attr(attr)) {
+            if provide != Self::Provider {
+                Err(syn::Error::new(
+                    attr.span(),
+                    "only one of `fallible` and `optional` can be used on an argument",
+                ))?;
+            }
+            provide = match &attr.meta {
+                Meta::Path(path) if path.is_ident(Self::OPTIONAL) => {
+                    match option_inner(ty) {
+                        None => Err(syn::Error::new(
+                            ty.span(),
+                            "`#[optional]` argument should be `Option<T>`",
+                        ))?,
+                        // `AnyProvider` only provide the owned types one by one
+                        Some(inner @ (Type::Reference(_) | Type::Tuple(_))) => {
+                            Err(syn::Error::new(
+                                inner.span(),
+                                "`#[optional]` not support reference or tuple, \
+                                 using one `#[optional]` argument for each owned type",
+                            ))?
+                        }
+                        Some(_) => {}
+                    }
+                    Self::Optional
+                }
+                Meta::Path(_) => Self::Fallible,
+                Meta::List(list) if list.path.is_ident(Self::FALLIBLE) => {
+                    attr.parse_args::<Token![async]>()?;
+                    Self::AsyncFallible
+                }
+                meta => Err(syn::Error::new(
+                    meta.span(),
+                    "expect `#[fallible]`, `#[fallible(async)]` or `#[optional]`",
+                ))?,
+            };
+        }
+        Ok(provide)
+    }
+
+    pub fn is_fallible(&self) -> bool {
+        matches!(self, Self::Fallible | Self::AsyncFallible)
+    }
+
+    /// remove all `#[fallible]` and `#[optional]` attributes on the fn arguments
+    pub fn strip_attrs(item_fn: &mut ItemFn) {
+        for arg in item_fn.sig.inputs.iter_mut() {
+            if let FnArg::Typed(PatType { attrs, .. }) = arg {
+                attrs.retain(|attr| !Self::is_arg_attr(attr))
+            }
+        }
+    }
+}
+
 pub struct ArgInfo {
     pub patten: Box<Pat>,
     pub ty: Box<Type>,
+    pub provide: ArgProvide,
 }
 
 impl ArgInfo {
-    fn new(pat: PatType) -> Self {
-        Self {
+    fn new(pat: PatType) -> syn::Result<Self> {
+        Ok(Self {
+            provide: ArgProvide::from_arg(&pat)?,
             patten: pat.pat,
             ty: pat.ty,
-        }
+        })
     }
 }
 
@@ -87,7 +167,7 @@ impl<'r> InputFn<'r> {
                     FnArg::Receiver(_) => None,
                     FnArg::Typed(pat_type) => Some(ArgInfo::new(pat_type)),
                 })
-                .collect(),
+                .collect::<syn::Result<_>>()?,
             generic,
             ret,
             fn_body: item.block.stmts.as_slice(),
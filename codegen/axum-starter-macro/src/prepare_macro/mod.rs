@@ -5,7 +5,10 @@ use syn::{punctuated::Punctuated, ItemFn, Lifetime};
 
 use self::{
     code_gen::CodeGen,
-    inputs::{attr_name::PrepareName, input_fn::InputFn},
+    inputs::{
+        attr_name::PrepareName,
+        input_fn::{ArgProvide, InputFn},
+    },
 };
 
 pub mod code_gen;
@@ -52,9 +55,11 @@ pub fn prepare_macro(
     }
 
     let input = InputFn::from_fn_item(&item_fn, lt.as_ref())?;
-    let code_gen = CodeGen::new(ident, lt, *prepare_mode, *may_fall, input);
+    let code_gen = CodeGen::new(ident, lt, *prepare_mode, *may_fall, input)?;
 
     let origin = if *origin {
+        let mut item_fn = item_fn.clone();
+        ArgProvide::strip_attrs(&mut item_fn);
         quote!(
             # item_fn
         )
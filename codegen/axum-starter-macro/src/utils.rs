@@ -1,5 +1,5 @@
 use heck::ToUpperCamelCase;
-use syn::{spanned::Spanned, Expr};
+use syn::{spanned::Spanned, Expr, GenericArgument, PathArguments, Type, TypePath};
 
 pub(crate) fn snake_to_upper(src: &str) -> String {
     ToUpperCamelCase::to_upper_camel_case(src)
@@ -22,9 +22,24 @@ pub fn check_callable_expr(expr: &Expr) -> Result<(), syn::Error> {
     }
 }
 
+/// the `T` of `Option<T>`
+pub fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let seg = path.segments.last().filter(|seg| seg.ident == "Option")?;
+    match &seg.arguments {
+        PathArguments::AngleBracketed(args) if args.args.len() == 1 => match args.args.first() {
+            Some(GenericArgument::Type(inner)) => Some(inner),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::utils::snake_to_upper;
+    use crate::utils::{option_inner, snake_to_upper};
 
     #[test]
     fn test() {
@@ -34,4 +49,13 @@ mod test {
         assert_eq!("AccBccDcc", snake_to_upper("acc_bcc_dcc"));
         assert_eq!("AccBccDcc", snake_to_upper("AccBccDcc"));
     }
+
+    #[test]
+    fn test_option_inner() {
+        let ty = syn::parse_quote!(Option<u16>);
+        assert_eq!(option_inner(&ty), Some(&syn::parse_quote!(u16)));
+        let ty = syn::parse_quote!(std::option::Option<String>);
+        assert_eq!(option_inner(&ty), Some(&syn::parse_quote!(String)));
+        assert_eq!(option_inner(&syn::parse_quote!(Vec<u16>)), None);
+    }
 }
@@ -0,0 +1,63 @@
+use std::path::Path;
+
+use axum_starter::{Cli, CliCommand, CliError, Configure, Provider};
+
+#[derive(Debug, Default, Provider, Configure)]
+#[conf(cli)]
+struct Conf {
+    /// the same field name as the `--config` of `Cli`
+    #[conf(cli(long = "conf-config"))]
+    config: Option<String>,
+    /// the same field name as the `--profile` of `Cli`
+    #[conf(cli(long = "conf-profile"))]
+    profile: Option<String>,
+    port: u16,
+}
+
+#[test]
+fn test_flag_not_collide() {
+    let cli = Cli::<Conf>::try_parse_from(
+        "server",
+        [
+            "server",
+            "--config",
+            "server.toml",
+            "--profile",
+            "dev",
+            "check-config",
+            "--conf-config",
+            "inner",
+            "--conf-profile",
+            "fast",
+            "--port",
+            "8080",
+        ],
+    )
+    .expect("bad args");
+    assert_eq!(cli.subcommand(), CliCommand::CheckConfig);
+    assert_eq!(cli.config_file(), Some(Path::new("server.toml")));
+    assert_eq!(cli.profile(), Some("dev"));
+
+    let err = cli
+        .load(|_| Ok::<_, std::io::Error>(Conf::default()))
+        .unwrap_err();
+    assert!(matches!(err, CliError::Finished(CliCommand::CheckConfig)));
+
+    let cli = Cli::<Conf>::try_parse_from(
+        "server",
+        ["server", "--conf-config", "inner", "--conf-profile", "fast"],
+    )
+    .expect("bad args");
+    let conf = cli
+        .load(|_| Ok::<_, std::io::Error>(Conf::default()))
+        .expect("serve");
+    assert_eq!(conf.config.as_deref(), Some("inner"));
+    assert_eq!(conf.profile.as_deref(), Some("fast"));
+    assert_eq!(conf.port, 0);
+
+    let err = Cli::<Conf>::try_parse_from("server", ["server", "--port", "not-port"])
+        .expect("bad args")
+        .load(|_| Ok::<_, std::io::Error>(Conf::default()))
+        .unwrap_err();
+    assert!(matches!(err, CliError::Args(_)));
+}
@@ -0,0 +1,63 @@
+use axum_starter::{Configure, LogReloadHandle, LoggerInitialization, Provider, SubscriberError};
+
+#[derive(Debug, Provider, Configure)]
+#[conf(logger(tracing_subscriber(level_from = "log_level", format_from = "log_format")))]
+struct Conf {
+    #[provider(default = "String::from(\"info\")")]
+    log_level: Option<String>,
+    // `None` keep the default format
+    #[provider(r#ref)]
+    log_format: Option<String>,
+}
+
+#[test]
+fn test_tracing_subscriber() {
+    let conf = Conf {
+        log_level: Some(String::from("debug,hyper=warn")),
+        log_format: None,
+    };
+    conf.init_logger().expect("init logger failure");
+
+    let handle = LogReloadHandle::get().expect("reload handle");
+    assert_eq!(handle.directives().as_deref(), Some("hyper=warn,debug"));
+
+    let conf = Conf {
+        log_level: None,
+        log_format: Some(String::from("yaml")),
+    };
+    assert!(matches!(
+        conf.init_logger(),
+        Err(SubscriberError::UnknownFormat(format)) if format == "yaml"
+    ));
+}
+
+#[derive(Debug, Provider, Configure)]
+#[conf(logger(tracing_subscriber(level_from = "level", format_from = "format")))]
+struct Renamed {
+    #[provider(rename = "FilterDirectives")]
+    level: String,
+    #[provider(rename = "OutputFormat", r#ref)]
+    format: String,
+}
+
+#[test]
+fn test_renamed_field() {
+    // read through the renamed wrappers, the invalid level is rejected before init
+    let conf = Renamed {
+        level: String::from("hyper=loud"),
+        format: String::from("json"),
+    };
+    assert!(matches!(
+        conf.init_logger(),
+        Err(SubscriberError::Directives(_))
+    ));
+
+    let conf = Renamed {
+        level: String::from("info"),
+        format: String::from("yaml"),
+    };
+    assert!(matches!(
+        conf.init_logger(),
+        Err(SubscriberError::UnknownFormat(format)) if format == "yaml"
+    ));
+}
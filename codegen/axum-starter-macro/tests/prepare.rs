@@ -0,0 +1,127 @@
+use std::{io, net::SocketAddr, sync::Arc};
+
+use axum_starter::{prepare, Prepare, PrepareArgError, Provider};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tls(&'static str);
+
+pub struct Missing;
+
+#[derive(Provider)]
+#[provider(optional = "Tls")]
+struct Conf {
+    #[provider(default = "8080")]
+    port: Option<u16>,
+    #[provider(default)]
+    workers: Option<usize>,
+}
+
+impl<'r> Provider<'r, Tls> for Conf {
+    fn provide(&'r self) -> Tls {
+        Tls("cert")
+    }
+}
+
+#[prepare(Listen)]
+fn listen(
+    Port(port): Port,
+    Workers(workers): Workers,
+    #[optional] tls: Option<Tls>,
+    #[optional] missing: Option<Missing>,
+) -> (u16, usize, Option<Tls>, bool) {
+    (port, workers, tls, missing.is_some())
+}
+
+#[tokio::test]
+async fn test_default_and_optional() {
+    let conf = Conf {
+        port: None,
+        workers: None,
+    };
+    let (port, workers, tls, missing) = Listen.prepare(Arc::new(conf)).await.unwrap();
+    assert_eq!((port, workers), (8080, 0));
+    // provided by the hand-written impl listed in `optional`
+    assert_eq!(tls, Some(Tls("cert")));
+    assert!(!missing);
+
+    let conf = Conf {
+        port: Some(80),
+        workers: Some(4),
+    };
+    let (port, workers, ..) = Listen.prepare(Arc::new(conf)).await.unwrap();
+    assert_eq!((port, workers), (80, 4));
+}
+
+#[derive(Provider)]
+struct UpstreamConf {
+    #[provider(map_to(ty = "SocketAddr", by = "|s: &String| s.parse()", fallible))]
+    upstream: String,
+    #[provider(map_to(ty = "Vec<u8>", by = "load_key", r#async))]
+    key_path: String,
+}
+
+async fn load_key(path: &str) -> Result<Vec<u8>, io::Error> {
+    match path {
+        "" => Err(io::Error::new(io::ErrorKind::NotFound, "empty key path")),
+        path => Ok(path.as_bytes().to_vec()),
+    }
+}
+
+#[prepare(Connect?)]
+async fn connect(
+    #[fallible] addr: SocketAddr,
+    #[fallible(async)] key: Vec<u8>,
+) -> Result<(SocketAddr, usize), io::Error> {
+    if addr.port() == 0 {
+        return Err(io::Error::other("port 0"));
+    }
+    Ok((addr, key.len()))
+}
+
+#[prepare(sync ParseAddr)]
+fn parse_addr(#[fallible] addr: SocketAddr) -> SocketAddr {
+    addr
+}
+
+fn upstream(upstream: &str, key_path: &str) -> Arc<UpstreamConf> {
+    Arc::new(UpstreamConf {
+        upstream: upstream.into(),
+        key_path: key_path.into(),
+    })
+}
+
+#[tokio::test]
+async fn test_fallible_arg() {
+    let ret = Connect.prepare(upstream("127.0.0.1:80", "key")).await;
+    assert_eq!(ret.unwrap(), ("127.0.0.1:80".parse().unwrap(), 3));
+
+    // the error names the argument failed
+    let err = Connect
+        .prepare(upstream("not an address", "key"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PrepareArgError::Provide { arg: "addr", .. }));
+    assert!(err
+        .to_string()
+        .starts_with("provide argument `addr` failure"));
+
+    let err = Connect
+        .prepare(upstream("127.0.0.1:80", ""))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PrepareArgError::Provide { arg: "key", .. }));
+    assert!(err.to_string().contains("empty key path"));
+
+    // the error of the prepare itself
+    let err = Connect
+        .prepare(upstream("127.0.0.1:0", "key"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PrepareArgError::Prepare(_)));
+
+    let err = ParseAddr
+        .prepare(upstream("not an address", "key"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, PrepareArgError::Provide { arg: "addr", .. }));
+}
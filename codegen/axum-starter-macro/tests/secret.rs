@@ -0,0 +1,44 @@
+use axum_starter::{Provider, Secret, TryProvider};
+
+#[derive(Debug, Provider)]
+struct Conf {
+    #[provider(secret)]
+    token: Secret<String>,
+    #[provider(secret(env = "AXUM_STARTER_TEST_API_KEY"))]
+    api_key: Secret<String>,
+}
+
+#[test]
+fn test_secret() {
+    let conf = Conf {
+        token: Secret::new("token".to_owned()),
+        api_key: Secret::new("from-config".to_owned()),
+    };
+    // the `print-config` output
+    let printed = format!("{conf:?}");
+    assert!(!printed.contains("token\""));
+    assert!(!printed.contains("from-config"));
+
+    let Token(token) = Provider::provide(&conf);
+    assert_eq!(token.expose_secret(), "token");
+
+    // fallback to the field
+    let ApiKey(key) = TryProvider::try_provide(&conf).unwrap();
+    assert_eq!(key.expose_secret(), "from-config");
+
+    std::env::set_var("AXUM_STARTER_TEST_API_KEY", "from-env");
+    let ApiKey(key) = TryProvider::try_provide(&conf).unwrap();
+    assert_eq!(key.expose_secret(), "from-env");
+
+    let path = std::env::temp_dir().join("axum_starter_test_api_key");
+    std::fs::write(&path, "from-file").unwrap();
+    std::env::set_var("AXUM_STARTER_TEST_API_KEY_FILE", &path);
+    let ApiKey(key) = TryProvider::try_provide(&conf).unwrap();
+    assert_eq!(key.expose_secret(), "from-file");
+
+    std::env::set_var(
+        "AXUM_STARTER_TEST_API_KEY_FILE",
+        path.with_extension("missing"),
+    );
+    assert!(<Conf as TryProvider<ApiKey>>::try_provide(&conf).is_err());
+}
@@ -59,6 +59,7 @@ impl FromStateCollector for MyState {
 }
 
 #[prepare(box origin Student)]
+#[allow(dead_code)]
 async fn arr(id: i32, name: &String) {
     println!("my name is {name} id is {id}");
 }
@@ -85,10 +86,14 @@ where
                 },
             ),
         ),
-        Route::new("/f/panic", get(|| async { panic!("Not a api") })),
+        Route::new("/f/panic", get(panic_handle)),
     )
 }
 
+async fn panic_handle() -> &'static str {
+    panic!("Not a api")
+}
+
 #[derive(Debug, Provider, Configure)]
 #[conf(
     logger(
@@ -250,10 +250,11 @@ impl PrepareDecorator for LoggerDecorator {
         Fut: Future<Output = Result<T, PrepareError>> + 'static,
         T: 'static,
     {
+        let addr = self.0;
         Box::pin(async move {
             match in_fut.await {
                 Ok(ret) => {
-                    info!("prepare[{src}] ret type is {}", type_name::<T>());
+                    info!("[{addr}] prepare[{src}] ret type is {}", type_name::<T>());
                     Ok(ret)
                 }
                 err @ Err(_) => err,
@@ -1,12 +1,9 @@
-use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::atomic::{AtomicI32, Ordering};
 use std::sync::Arc;
 
 use axum::extract::{FromRef, State};
 use axum_starter::state::AddState;
-use axum_starter::{
-    prepare, Configure, FromStateCollector, PrepareStateEffect, Provider, ServerPrepare,
-};
+use axum_starter::{prepare, Configure, FromStateCollector, Provider, ServerPrepare};
 use http::Request;
 use tower::{Service, ServiceExt};
 use tower_http::trace::TraceLayer;
@@ -20,6 +17,7 @@ async fn task() {
     let mut service = ServerPrepare::test_with_config(Configure::default())
         .init_logger()
         .expect("init Logger Failure")
+        .typed_state()
         .prepare_state(AddCounter)
         .convert_state::<TestState>()
         .layer(TraceLayer::new_for_http())
@@ -71,7 +69,8 @@ async fn handle(State(counter): State<Arc<AtomicI32>>) -> String {
 
 // prepare start
 
+// return the concrete effect type, thus the `typed_state` can track it
 #[prepare(AddCounter)]
-fn prepare_counter() -> impl PrepareStateEffect {
+fn prepare_counter() -> AddState<Arc<AtomicI32>> {
     AddState(Arc::new(AtomicI32::new(2)))
 }
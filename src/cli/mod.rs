@@ -0,0 +1,270 @@
+use std::{
+    error,
+    ffi::OsString,
+    fmt::Debug,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use clap::{Arg, ArgMatches, Command};
+
+use crate::{ConfigProfiles, Profile};
+
+const CONFIG_ARG: &str = "config";
+const PROFILE_ARG: &str = "profile";
+
+/// the config which can be updated by command line flags
+///
+/// using `#[conf(cli)]` on [`Configure`](axum_starter_macro::Configure) derive to implement it
+pub trait CliConfigure {
+    /// adding the flags of this config into the [Command]
+    fn augment_args(command: Command) -> Command;
+
+    /// update this config by the parsed flags
+    fn update_from_args(&mut self, matches: &ArgMatches) -> Result<(), clap::Error>;
+}
+
+/// the built-in subcommands
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CliCommand {
+    /// start the server, the default subcommand
+    Serve,
+    /// load the config and apply the flags then exit, only checking the config can be parsed
+    ///
+    /// the prepares are not executed, thus the invalid values like an unreachable database
+    /// are reported by `serve`
+    CheckConfig,
+    /// load the config then print it with [Debug]
+    ///
+    /// declare the secret fields as [Secret](crate::Secret) to redact them
+    PrintConfig,
+}
+
+impl CliCommand {
+    fn name(&self) -> &'static str {
+        match self {
+            CliCommand::Serve => "serve",
+            CliCommand::CheckConfig => "check-config",
+            CliCommand::PrintConfig => "print-config",
+        }
+    }
+
+    fn about(&self) -> &'static str {
+        match self {
+            CliCommand::Serve => "start the server",
+            CliCommand::CheckConfig => {
+                "check whether the config can be parsed, the prepares are not executed"
+            }
+            CliCommand::PrintConfig => "print the loaded config",
+        }
+    }
+}
+
+/// the parsed command line of the server
+///
+/// ```rust
+/// use axum_starter::{Cli, CliCommand, CliError, Configure, Provider};
+///
+/// #[derive(Debug, Default, Provider, Configure)]
+/// #[conf(cli)]
+/// struct Conf {
+///     /// the port server listen on
+///     port: u16,
+///     log_level: Option<String>,
+///     // `provider(skip)` or `conf(cli(skip))` field will not have the flag
+///     #[provider(skip)]
+///     hosts: Vec<String>,
+/// }
+///
+/// let cli = Cli::<Conf>::try_parse_from(
+///     "server",
+///     ["server", "serve", "--port", "8080", "--log-level", "debug"],
+/// )
+/// .expect("bad args");
+/// assert_eq!(cli.subcommand(), CliCommand::Serve);
+///
+/// let conf = match cli.load(|_config_file| Ok::<_, std::io::Error>(Conf::default())) {
+///     Ok(conf) => conf,
+///     // `check-config` or `print-config` done
+///     Err(CliError::Finished(_)) => return,
+///     Err(err) => panic!("{err}"),
+/// };
+/// // `serve` using the conf start `ServerPrepare`
+/// assert_eq!(conf.port, 8080);
+/// assert_eq!(conf.log_level.as_deref(), Some("debug"));
+/// ```
+pub struct Cli<C> {
+    command: CliCommand,
+    matches: ArgMatches,
+    _phantom: PhantomData<C>,
+}
+
+impl<C: CliConfigure> Cli<C> {
+    /// the [Command] with the built-in subcommands and flags
+    ///
+    /// - `--config <PATH>` the config file path
+    /// - `--profile <NAME>` the [`Profile`](crate::Profile) to load
+    /// - the flags provided by [CliConfigure]
+    pub fn command(name: &'static str) -> Command {
+        let command = Command::new(name)
+            .arg(
+                Arg::new(CONFIG_ARG)
+                    .long(CONFIG_ARG)
+                    .value_name("PATH")
+                    .value_parser(clap::value_parser!(PathBuf))
+                    .global(true)
+                    .help("the config file path"),
+            )
+            .arg(
+                Arg::new(PROFILE_ARG)
+                    .long(PROFILE_ARG)
+                    .value_name("NAME")
+                    .global(true)
+                    .help("the config profile to load"),
+            )
+            .subcommands(
+                [
+                    CliCommand::Serve,
+                    CliCommand::CheckConfig,
+                    CliCommand::PrintConfig,
+                ]
+                .map(|cmd| Command::new(cmd.name()).about(cmd.about())),
+            );
+        C::augment_args(command)
+    }
+
+    /// parse from [`std::env::args_os`], exit on error
+    pub fn parse(name: &'static str) -> Self {
+        Self::from_matches(Self::command(name).get_matches())
+    }
+
+    /// parse from the giving args
+    pub fn try_parse_from<I, T>(name: &'static str, args: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        Self::command(name)
+            .try_get_matches_from(args)
+            .map(Self::from_matches)
+    }
+
+    fn from_matches(matches: ArgMatches) -> Self {
+        let command = match matches.subcommand_name() {
+            Some("check-config") => CliCommand::CheckConfig,
+            Some("print-config") => CliCommand::PrintConfig,
+            _ => CliCommand::Serve,
+        };
+        Self {
+            command,
+            matches,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// the subcommand to run
+    pub fn subcommand(&self) -> CliCommand {
+        self.command
+    }
+
+    /// the config file path from `--config`
+    pub fn config_file(&self) -> Option<&Path> {
+        self.matches
+            .get_one::<PathBuf>(CONFIG_ARG)
+            .map(PathBuf::as_path)
+    }
+
+    /// the profile name from `--profile`
+    pub fn profile(&self) -> Option<&str> {
+        self.matches
+            .get_one::<String>(PROFILE_ARG)
+            .map(String::as_str)
+    }
+
+    /// load the config with the config file path, then apply the command line flags on it
+    ///
+    /// - `serve` return the config for starting the server
+    /// - `check-config` and `print-config` return [CliError::Finished] when done,
+    ///   the config is only parsed, without executing the prepares
+    pub fn load<F, E>(self, load: F) -> Result<C, CliError<E>>
+    where
+        F: FnOnce(Option<&Path>) -> Result<C, E>,
+        E: error::Error + 'static,
+        C: Debug,
+    {
+        let config = load(self.config_file()).map_err(CliError::Load)?;
+        self.finish(config)
+    }
+
+    /// like [Cli::load], but load the [ConfigProfiles] then merge the profile
+    /// selected by [Profile::select] from `--profile` or the environment variable `env_key`
+    ///
+    /// ```rust
+    /// use axum_starter::{Cli, ConfigProfiles, Configure, Profile, Provider};
+    ///
+    /// #[derive(Debug, Default, Provider, Configure)]
+    /// #[conf(cli)]
+    /// struct Conf {
+    ///     port: u16,
+    ///     #[provider(skip)]
+    ///     profile: Profile,
+    /// }
+    ///
+    /// impl AsMut<Profile> for Conf {
+    ///     fn as_mut(&mut self) -> &mut Profile {
+    ///         &mut self.profile
+    ///     }
+    /// }
+    ///
+    /// let cli = Cli::<Conf>::try_parse_from("server", ["server", "--profile", "dev"]).unwrap();
+    /// let conf = cli
+    ///     .load_profiles("APP_PROFILE", |_config_file| {
+    ///         Ok::<_, std::io::Error>(
+    ///             ConfigProfiles::new(Conf::default()).overlay("dev", "dev overlay", |conf| {
+    ///                 conf.port = 8080
+    ///             }),
+    ///         )
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(conf.port, 8080);
+    /// assert!(conf.profile.is("dev"));
+    /// ```
+    pub fn load_profiles<F, E>(self, env_key: &str, load: F) -> Result<C, CliError<E>>
+    where
+        F: FnOnce(Option<&Path>) -> Result<ConfigProfiles<C>, E>,
+        E: error::Error + 'static,
+        C: Debug + AsMut<Profile>,
+    {
+        let profiles = load(self.config_file()).map_err(CliError::Load)?;
+        let config = profiles.load_selected(self.profile(), env_key);
+        self.finish(config)
+    }
+
+    /// apply the command line flags on the config, then run the subcommand
+    fn finish<E>(self, mut config: C) -> Result<C, CliError<E>>
+    where
+        E: error::Error + 'static,
+        C: Debug,
+    {
+        config.update_from_args(&self.matches)?;
+
+        match self.command {
+            CliCommand::Serve => return Ok(config),
+            CliCommand::CheckConfig => println!("config is parsed"),
+            CliCommand::PrintConfig => println!("{config:#?}"),
+        }
+        Err(CliError::Finished(self.command))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+/// error while loading config by [Cli]
+pub enum CliError<E: error::Error + 'static> {
+    #[error("load config failure: {0}")]
+    Load(#[source] E),
+    #[error(transparent)]
+    Args(#[from] clap::Error),
+    /// the subcommand other than `serve` is done, the server should not start
+    #[error("subcommand `{}` finished", .0.name())]
+    Finished(CliCommand),
+}
@@ -1,8 +1,17 @@
+pub mod profile;
 pub mod provider;
+pub mod secret;
+pub mod try_provider;
 
 #[cfg(test)]
 mod test {
-    use super::provider::Provider;
+    use std::io::Write;
+
+    use super::{
+        profile::{ConfigProfiles, Profile},
+        provider::Provider,
+        secret::Secret,
+    };
 
     // config and provide
     #[derive(Debug, Clone, Copy)]
@@ -10,6 +19,20 @@ mod test {
 
     struct Config {
         db: Database,
+        profile: Profile,
+    }
+
+    impl AsMut<Profile> for Config {
+        fn as_mut(&mut self) -> &mut Profile {
+            &mut self.profile
+        }
+    }
+
+    // the config can provide the profile in its own way
+    impl<'r> Provider<'r, Profile> for Config {
+        fn provide(&'r self) -> Profile {
+            self.profile.clone()
+        }
     }
 
     impl<'r> Provider<'r, Database> for Config {
@@ -26,8 +49,49 @@ mod test {
 
     #[test]
     fn test_nest_config() {
-        let config = Config { db: Database };
+        let config = Config {
+            db: Database,
+            profile: Profile::default(),
+        };
 
         let _data = <Config as Provider<(Database, Database, Database)>>::provide(&config);
     }
+
+    #[test]
+    fn test_secret_redacted() {
+        let secret = Secret::new(String::from("p@ssw0rd"));
+
+        assert_eq!(format!("{secret:?}"), "Secret([REDACTED])");
+        assert_eq!(format!("{secret}"), "[REDACTED]");
+        assert_eq!(secret.expose_secret(), "p@ssw0rd");
+    }
+
+    #[test]
+    fn test_secret_from_env_file() {
+        let path = std::env::temp_dir().join("axum_starter_secret_test");
+        let mut file = std::fs::File::create(&path).unwrap();
+        writeln!(file, "p@ssw0rd").unwrap();
+
+        std::env::set_var("AXUM_STARTER_SECRET_TEST_FILE", &path);
+        let secret = Secret::from_env("AXUM_STARTER_SECRET_TEST").unwrap();
+        assert_eq!(secret.expose_secret(), "p@ssw0rd");
+
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_profile_overlay() {
+        let config = ConfigProfiles::new(Config {
+            db: Database,
+            profile: Profile::default(),
+        })
+        .base_source("base")
+        .overlay("dev", "dev overlay", |_| ())
+        .overlay("prod", "prod overlay", |_| unreachable!())
+        .load("dev");
+
+        let profile = <Config as Provider<Profile>>::provide(&config);
+        assert!(profile.is("dev"));
+        assert_eq!(profile.sources(), ["base", "dev overlay"]);
+    }
 }
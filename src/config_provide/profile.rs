@@ -0,0 +1,214 @@
+use std::{
+    env,
+    fmt::{Display, Formatter},
+};
+
+use crate::prepare_behave::{effect_collectors::state_collector::Aggregate, StateCollector};
+
+const DEFAULT_PROFILE: &str = "default";
+
+/// the active config profile, for instance `dev`, `staging` or `prod`
+///
+/// it is stored into the config by [ConfigProfiles::load], thus the config can provide it
+/// and prepares can branch on it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Profile {
+    name: String,
+    sources: Vec<String>,
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self {
+            name: DEFAULT_PROFILE.to_owned(),
+            sources: Vec::new(),
+        }
+    }
+}
+
+impl Profile {
+    /// the name of the profile
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the config sources applied in order, base source first
+    pub fn sources(&self) -> &[String] {
+        &self.sources
+    }
+
+    /// whether the profile is the giving one
+    pub fn is(&self, name: &str) -> bool {
+        self.name == name
+    }
+
+    /// select the profile name
+    ///
+    /// 1. the `cli` name, like the `--profile` parsed by [`Cli`](crate::Cli)
+    /// 2. the environment variable `env_key`
+    /// 3. `default`
+    pub fn select(cli: Option<&str>, env_key: &str) -> String {
+        cli.map(ToOwned::to_owned)
+            .or_else(|| env::var(env_key).ok())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_owned())
+    }
+}
+
+impl Display for Profile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.name, self.sources.join(", "))
+    }
+}
+
+/// the [Profile] reported by [`ServerPrepare::report_profile`](crate::ServerPrepare::report_profile)
+#[derive(Debug, Default)]
+pub(crate) struct ReportProfile(Option<Profile>);
+
+impl ReportProfile {
+    pub(crate) fn new(profile: Profile) -> Self {
+        Self(Some(profile))
+    }
+
+    pub(crate) fn take(collector: &mut StateCollector) -> Option<Profile> {
+        collector
+            .take::<Self>()
+            .ok()
+            .and_then(|ReportProfile(profile)| profile)
+    }
+}
+
+impl Aggregate for ReportProfile {
+    fn merge(&mut self, other: Self) {
+        if other.0.is_some() {
+            *self = other;
+        }
+    }
+}
+
+struct Overlay<C> {
+    profile: String,
+    source: String,
+    apply: Box<dyn FnOnce(&mut C)>,
+}
+
+/// base config with per-profile overlays
+///
+/// the config holds the loaded [Profile] by [AsMut], thus it can provide the profile
+///
+/// ```rust
+/// use axum_starter::{ConfigProfiles, Profile, Provider};
+///
+/// #[derive(Default, Provider)]
+/// struct Conf {
+///     port: u16,
+///     debug: bool,
+///     #[provider(transparent)]
+///     profile: Profile,
+/// }
+///
+/// impl AsMut<Profile> for Conf {
+///     fn as_mut(&mut self) -> &mut Profile {
+///         &mut self.profile
+///     }
+/// }
+///
+/// let conf = ConfigProfiles::new(Conf { port: 80, ..Default::default() })
+///     .overlay("dev", "dev overlay", |conf| {
+///         conf.port = 8080;
+///         conf.debug = true;
+///     })
+///     .load("dev");
+///
+/// assert_eq!(conf.port, 8080);
+/// assert!(conf.debug);
+/// assert!(Provider::<Profile>::provide(&conf).is("dev"));
+/// ```
+pub struct ConfigProfiles<C> {
+    base: C,
+    base_source: String,
+    overlays: Vec<Overlay<C>>,
+}
+
+impl<C> ConfigProfiles<C> {
+    pub fn new(base: C) -> Self {
+        Self {
+            base,
+            base_source: DEFAULT_PROFILE.to_owned(),
+            overlays: Vec::new(),
+        }
+    }
+
+    /// set the description of where the base config come from, for instance the file path
+    pub fn base_source(self, source: impl Into<String>) -> Self {
+        Self {
+            base_source: source.into(),
+            ..self
+        }
+    }
+
+    /// adding an overlay for the `profile`
+    ///
+    /// multiply overlays of the same profile will be applied in adding order
+    pub fn overlay<F>(
+        mut self,
+        profile: impl Into<String>,
+        source: impl Into<String>,
+        overlay: F,
+    ) -> Self
+    where
+        F: FnOnce(&mut C) + 'static,
+    {
+        self.overlays.push(Overlay {
+            profile: profile.into(),
+            source: source.into(),
+            apply: Box::new(overlay),
+        });
+        self
+    }
+
+    /// merge the overlays of `profile` onto the base config, then store the [Profile] into it
+    pub fn load(self, profile: impl Into<String>) -> C
+    where
+        C: AsMut<Profile>,
+    {
+        let Self {
+            mut base,
+            base_source,
+            overlays,
+        } = self;
+        let name = profile.into();
+        let mut sources = vec![base_source];
+
+        for Overlay { source, apply, .. } in overlays
+            .into_iter()
+            .filter(|overlay| overlay.profile == name)
+        {
+            apply(&mut base);
+            sources.push(source);
+        }
+
+        let profile = Profile { name, sources };
+        info!(config.profile = %profile, "Config Loaded");
+        *base.as_mut() = profile;
+        base
+    }
+
+    /// load the profile selected by [Profile::select]
+    pub fn load_selected(self, cli: Option<&str>, env_key: &str) -> C
+    where
+        C: AsMut<Profile>,
+    {
+        self.load(Profile::select(cli, env_key))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Profile;
+
+    #[test]
+    fn test_select() {
+        assert_eq!(Profile::select(Some("prod"), "NOT_EXIST"), "prod");
+        assert_eq!(Profile::select(None, "NOT_EXIST"), "default");
+    }
+}
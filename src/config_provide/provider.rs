@@ -1,3 +1,5 @@
+use std::any::{Any, TypeId};
+
 /// the Config that can provide config info `T`
 /// the T can be either returning Ownership or Reference
 pub trait Provider<'r, T: 'r> {
@@ -32,14 +34,23 @@ macro_rules! group_provider {
     };
 }
 
-group_provider!();
-group_provider!(T1);
-group_provider!(T1, T2);
-group_provider!(T1, T2, T3);
-group_provider!(T1, T2, T3, T4);
-group_provider!(T1, T2, T3, T4, T5);
-group_provider!(T1, T2, T3, T4, T5, T6);
-group_provider!(T1, T2, T3, T4, T5, T6, T7);
-group_provider!(T1, T2, T3, T4, T5, T6, T7, T8);
-group_provider!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
-group_provider!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+all_tuples!(group_provider);
+
+/// the Config that can provide config info by [TypeId] at runtime
+///
+/// it is implemented by [`Provider`](axum_starter_macro::Provider) derive with all owned provide types,
+/// thus the `#[optional]` argument of [`prepare`](axum_starter_macro::prepare) can be [None]
+/// when the Config not provide it at all
+pub trait AnyProvider {
+    fn provide_any(&self, id: TypeId) -> Option<Box<dyn Any>>;
+
+    /// provide `T` if the Config can provide it, otherwise [None]
+    fn provide_optional<T: 'static>(&self) -> Option<T>
+    where
+        Self: Sized,
+    {
+        self.provide_any(TypeId::of::<T>())
+            .and_then(|value| value.downcast().ok())
+            .map(|value| *value)
+    }
+}
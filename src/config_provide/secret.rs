@@ -0,0 +1,121 @@
+use std::{
+    env,
+    fmt::{Debug, Display, Formatter},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
+
+use zeroize::Zeroize;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// a sensitive config value, for instance the password of Database or an Api key
+///
+/// - [Debug] and [Display] will always output `[REDACTED]`, thus it will not leak in
+///   `#[derive(Debug)]` config or the `tracing` output
+/// - the inner value can only be accessed by [Secret::expose_secret]
+/// - the inner value will be zeroized on drop
+pub struct Secret<T: Zeroize>(T);
+
+impl<T: Zeroize> Secret<T> {
+    pub fn new(secret: T) -> Self {
+        Self(secret)
+    }
+
+    /// explicit access the inner secret value
+    pub fn expose_secret(&self) -> &T {
+        &self.0
+    }
+}
+
+impl Secret<String> {
+    /// load secret from environment variable `key`
+    ///
+    /// if the environment variable `{key}_FILE` exist, the secret will be
+    /// loaded from the file it point to instead. The trailing line break of the file will be trimmed
+    pub fn from_env(key: &str) -> Result<Self, SecretLoadError> {
+        match env::var_os(format!("{key}_FILE")) {
+            Some(path) => Self::from_file(path),
+            None => env::var(key)
+                .map(Self::new)
+                .map_err(|_| SecretLoadError::NotPresent(key.to_owned())),
+        }
+    }
+
+    /// load secret from a file, the trailing line break will be trimmed
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, SecretLoadError> {
+        let mut content = fs::read_to_string(path)?;
+        let len = content.trim_end_matches(['\r', '\n']).len();
+        content.truncate(len);
+        Ok(Self::new(content))
+    }
+}
+
+impl<T: Zeroize + FromStr> FromStr for Secret<T> {
+    type Err = T::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        T::from_str(s).map(Self::new)
+    }
+}
+
+impl<T: Zeroize> From<T> for Secret<T> {
+    fn from(secret: T) -> Self {
+        Self::new(secret)
+    }
+}
+
+impl<T: Zeroize + Clone> Clone for Secret<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T: Zeroize + Default> Default for Secret<T> {
+    fn default() -> Self {
+        Self(T::default())
+    }
+}
+
+impl<T: Zeroize> Drop for Secret<T> {
+    fn drop(&mut self) {
+        self.0.zeroize()
+    }
+}
+
+impl<T: Zeroize> Debug for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Secret")
+            .field(&format_args!("{REDACTED}"))
+            .finish()
+    }
+}
+
+impl<T: Zeroize> Display for Secret<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(REDACTED)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T> serde::Deserialize<'de> for Secret<T>
+where
+    T: Zeroize + serde::Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(Self::new)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+/// error while loading [Secret] from environment or file
+pub enum SecretLoadError {
+    #[error("Secret environment variable {0} Not Present")]
+    NotPresent(String),
+    #[error(transparent)]
+    IO(#[from] io::Error),
+}
@@ -0,0 +1,56 @@
+use std::{
+    error,
+    fmt::{Debug, Display, Formatter},
+};
+
+use futures::Future;
+
+/// the Config that can provide config info `T`, but the providing may fail
+///
+/// for instance parsing a `Url` from a config string
+pub trait TryProvider<'r, T: 'r> {
+    type Error: error::Error + 'static;
+
+    fn try_provide(&'r self) -> Result<T, Self::Error>;
+}
+
+/// async version of [TryProvider]
+///
+/// for instance loading a secret from a local vault file
+pub trait AsyncTryProvider<'r, T: 'r> {
+    type Error: error::Error + 'static;
+    type Future: Future<Output = Result<T, Self::Error>> + 'r;
+
+    fn try_provide_async(&'r self) -> Self::Future;
+}
+
+/// the error of [TryProvider] and [AsyncTryProvider] generated by
+/// [`Provider`](axum_starter_macro::Provider) derive
+pub struct ProvideError(Box<dyn error::Error + Send + Sync>);
+
+impl ProvideError {
+    pub fn new<E>(err: E) -> Self
+    where
+        E: Into<Box<dyn error::Error + Send + Sync>>,
+    {
+        Self(err.into())
+    }
+}
+
+impl Debug for ProvideError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.0, f)
+    }
+}
+
+impl Display for ProvideError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl error::Error for ProvideError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        self.0.source()
+    }
+}
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::Extension,
+    response::{IntoResponse, Response},
+    routing::{get, MethodRouter},
+    Json, Router,
+};
+use http::{header::AUTHORIZATION, HeaderMap, StatusCode};
+use serde_json::{json, Value};
+
+use crate::{
+    phase::{MiddlewareOrder, Phase},
+    prepare_behave::{
+        effect_collectors::state_inventory::StateInventory, effect_traits::PrepareRouteEffect,
+    },
+    Secret, StateCollector,
+};
+
+/// [PrepareRouteEffect] serving the [StateInventory] as JSON, require `debug-state` feature
+///
+/// the collected states with the prepares producing them, and the duration of each prepare.
+/// The request need header `Authorization: Bearer <token>`, otherwise `401 Unauthorized`
+///
+/// ## Note
+/// the inventory is snapshot before converting into the State
+pub struct DebugState {
+    path: &'static str,
+    token: Arc<Secret<String>>,
+}
+
+impl DebugState {
+    pub fn new(path: &'static str, token: Secret<String>) -> Self {
+        Self {
+            path,
+            token: Arc::new(token),
+        }
+    }
+
+    /// the handler serving the inventory, can be the handler of `preparing_test`
+    pub fn method_router<S>(&self) -> MethodRouter<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let token = Arc::clone(&self.token);
+        get(
+            |headers: HeaderMap, Extension(inventory): Extension<Arc<StateInventory>>| async move {
+                debug_state(&token, &headers, &inventory)
+            },
+        )
+    }
+}
+
+impl<S> PrepareRouteEffect<S> for DebugState
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn set_route(self, route: Router<S>) -> Router<S> {
+        route.route(self.path, self.method_router())
+    }
+}
+
+fn debug_state(
+    token: &Secret<String>,
+    headers: &HeaderMap,
+    inventory: &StateInventory,
+) -> Response {
+    let authorized = headers
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .is_some_and(|bearer| constant_eq(bearer.as_bytes(), token.expose_secret().as_bytes()));
+    if !authorized {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    Json(inventory_json(inventory)).into_response()
+}
+
+/// the inventory for [DebugState] in the request extensions, as the innermost middleware
+pub(crate) fn add_inventory(order: &mut MiddlewareOrder, collector: &StateCollector) {
+    order.builtin(
+        "axum_starter::router::DebugState",
+        Phase::Innermost,
+        Extension(Arc::new(collector.inventory())),
+    );
+}
+
+fn inventory_json(inventory: &StateInventory) -> Value {
+    json!({
+        "states": inventory.states.iter().map(|state| json!({
+            "name": state.name,
+            "producer": state.producer,
+        })).collect::<Vec<_>>(),
+        "prepares": inventory.prepares.iter().map(|prepare| json!({
+            "name": prepare.name,
+            "duration_ms": prepare.duration.as_secs_f64() * 1000.0,
+        })).collect::<Vec<_>>(),
+    })
+}
+
+/// compare without short circuit, thus the token can not be guessed by timing
+fn constant_eq(l: &[u8], r: &[u8]) -> bool {
+    l.len() == r.len() && l.iter().zip(r).fold(0, |acc, (l, r)| acc | (l ^ r)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use http::{header::AUTHORIZATION, HeaderMap, StatusCode};
+
+    use super::{debug_state, inventory_json};
+    use crate::{
+        prepare_behave::effect_collectors::state_inventory::{
+            PrepareRecord, StateInventory, StateRecord,
+        },
+        Secret,
+    };
+
+    #[test]
+    fn test_debug_state() {
+        let inventory = StateInventory {
+            states: vec![StateRecord {
+                name: "u8".into(),
+                producer: Some("AddU8"),
+            }],
+            prepares: vec![PrepareRecord {
+                name: "AddU8",
+                stage: 0,
+                started: Instant::now(),
+                duration: Duration::from_millis(2),
+            }],
+        };
+        let token = Secret::new(String::from("token"));
+
+        let resp = debug_state(&token, &HeaderMap::new(), &inventory);
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, "Bearer token".parse().unwrap());
+        let resp = debug_state(&token, &headers, &inventory);
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let json = inventory_json(&inventory);
+        assert_eq!(json["states"][0]["producer"], "AddU8");
+        assert_eq!(json["prepares"][0]["duration_ms"], 2.0);
+    }
+
+    #[cfg(feature = "test-utils")]
+    #[tokio::test]
+    async fn test_preparing_test() {
+        use std::convert::Infallible;
+
+        use axum::body::Body;
+        use http::Request;
+        use serde_json::Value;
+        use tower::ServiceExt;
+
+        use super::DebugState;
+        use crate::{state::AddState, ServerPrepare};
+
+        async fn add_u8(_: std::sync::Arc<()>) -> Result<AddState<u8>, Infallible> {
+            Ok(AddState::new(1))
+        }
+
+        let service = ServerPrepare::test_with_config(())
+            .prepare_state(add_u8)
+            .convert_state::<(u8,)>()
+            .preparing_test(
+                DebugState::new("/debug/state", Secret::new(String::from("token"))).method_router(),
+            )
+            .await
+            .expect("preparing failure");
+
+        let request = Request::get("/debug/state")
+            .header(AUTHORIZATION, "Bearer token")
+            .body(Body::empty())
+            .unwrap();
+        let resp = service.oneshot(request).await.unwrap();
+        let json = resp.json::<Value>().await.unwrap();
+        assert_eq!(json["states"].as_array().map(Vec::len), Some(1));
+        assert_eq!(json["prepares"].as_array().map(Vec::len), Some(1));
+    }
+}
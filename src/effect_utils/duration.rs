@@ -0,0 +1,55 @@
+use std::time::Duration;
+
+use serde::{de::Error, Deserialize, Deserializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Secs(f64),
+    Text(String),
+}
+
+fn parse<E: Error>(repr: Repr) -> Result<Duration, E> {
+    let (value, unit) = match repr {
+        Repr::Secs(secs) => (secs, 1.0),
+        Repr::Text(text) => {
+            let text = text.trim();
+            let split = text
+                .find(|c: char| c.is_ascii_alphabetic())
+                .unwrap_or(text.len());
+            let (value, unit) = text.split_at(split);
+            let unit = match unit {
+                "ms" => 0.001,
+                "" | "s" => 1.0,
+                "m" => 60.0,
+                "h" => 3600.0,
+                _ => return Err(E::custom(format!("unknown duration unit `{unit}`"))),
+            };
+            let value = value
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| E::custom(format!("invalid duration `{text}`")))?;
+            (value, unit)
+        }
+    };
+    Duration::try_from_secs_f64(value * unit).map_err(E::custom)
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+    parse(Repr::deserialize(deserializer)?)
+}
+
+#[cfg_attr(not(feature = "middleware"), allow(dead_code))]
+pub mod option {
+    use std::time::Duration;
+
+    use serde::{Deserialize, Deserializer};
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Option::<super::Repr>::deserialize(deserializer)?
+            .map(super::parse)
+            .transpose()
+    }
+}
@@ -0,0 +1,348 @@
+use std::{
+    error,
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+    time::Duration,
+};
+
+use futures::{
+    future::{join_all, BoxFuture},
+    FutureExt,
+};
+use tower::layer::util::Identity;
+
+use crate::prepare_behave::{
+    effect_collectors::state_collector::Aggregate,
+    effect_traits::{PrepareMiddlewareEffect, PrepareStateEffect},
+    StateCollector,
+};
+
+type BoxError = Box<dyn error::Error + Send + Sync>;
+type CheckFn = Arc<dyn Fn() -> BoxFuture<'static, Result<(), BoxError>> + Send + Sync>;
+
+#[derive(Default)]
+struct HealthInner {
+    ready: AtomicBool,
+    checks: RwLock<Vec<(String, CheckFn)>>,
+}
+
+/// the health of the server, the readiness and the health checks registered by prepares
+///
+/// it is always in the [StateCollector], thus the State can hold it for the health endpoints.
+/// - ready after all `post_prepare` tasks finished
+/// - not ready once the graceful shutdown signal arrive
+#[derive(Clone, Default)]
+pub struct Health {
+    inner: Arc<HealthInner>,
+}
+
+/// the result of a health check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckStatus {
+    pub name: String,
+    /// the failure reason, [None] if healthy
+    pub error: Option<String>,
+}
+
+impl Health {
+    /// register a health check, for instance ping the database
+    pub fn register<F, Fut, E>(&self, name: impl Into<String>, check: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        let check: CheckFn = Arc::new(move || check().map(|ret| ret.map_err(Into::into)).boxed());
+        self.inner
+            .checks
+            .write()
+            .expect("health checks poisoned")
+            .push((name.into(), check));
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.inner.ready.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_ready(&self, ready: bool) {
+        debug!(health.ready = ready);
+        self.inner.ready.store(ready, Ordering::Release)
+    }
+
+    /// run all health checks concurrently, the check longer than `timeout` is failure
+    pub async fn check(&self, timeout: Duration) -> Vec<CheckStatus> {
+        let checks = self
+            .inner
+            .checks
+            .read()
+            .expect("health checks poisoned")
+            .clone();
+        join_all(checks.into_iter().map(|(name, check)| async move {
+            let error = match tokio::time::timeout(timeout, check()).await {
+                Ok(Ok(())) => None,
+                Ok(Err(err)) => Some(err.to_string()),
+                Err(_) => Some(format!("timeout after {timeout:?}")),
+            };
+            CheckStatus { name, error }
+        }))
+        .await
+    }
+
+    /// the [Health] in the collector, insert one if not exist
+    pub(crate) fn from_collector(collector: &mut StateCollector) -> Self {
+        collector.aggregate::<Health>().clone()
+    }
+}
+
+impl Aggregate for Health {
+    fn merge(&mut self, other: Self) {
+        if Arc::ptr_eq(&self.inner, &other.inner) {
+            return;
+        }
+        let checks =
+            std::mem::take(&mut *other.inner.checks.write().expect("health checks poisoned"));
+        self.inner
+            .checks
+            .write()
+            .expect("health checks poisoned")
+            .extend(checks);
+    }
+}
+
+/// [PrepareStateEffect] or [PrepareMiddlewareEffect] registering a check into [Health]
+pub struct AddHealthCheck(Box<dyn FnOnce(&Health) + Send>);
+
+impl AddHealthCheck {
+    pub fn new<F, Fut, E>(name: impl Into<String>, check: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        let name = name.into();
+        Self(Box::new(move |health| {
+            debug!(health.check = name, "Register Health Check");
+            health.register(name, check)
+        }))
+    }
+}
+
+impl<Service> PrepareMiddlewareEffect<Service> for AddHealthCheck {
+    type Middleware = Identity;
+
+    fn take(self, states: &mut StateCollector) -> Self::Middleware {
+        self.take_state(states);
+        Identity::new()
+    }
+}
+
+impl PrepareStateEffect for AddHealthCheck {
+    fn take_state(self, states: &mut StateCollector) {
+        (self.0)(&Health::from_collector(states))
+    }
+}
+
+#[cfg(feature = "health")]
+pub use self::routes::HealthRoutes;
+
+#[cfg(feature = "health")]
+mod routes {
+    use std::time::Duration;
+
+    use axum::{
+        extract::{FromRef, State},
+        response::{IntoResponse, Response},
+        routing::get,
+        Json, Router,
+    };
+    use http::StatusCode;
+    use serde_json::json;
+
+    use super::Health;
+    use crate::prepare_behave::effect_traits::PrepareRouteEffect;
+
+    /// [PrepareRouteEffect] mounting the liveness and readiness endpoints, require `health` feature
+    ///
+    /// - liveness always `200 OK` while the server running
+    /// - readiness `200 OK` if [Health] is ready and all checks pass, otherwise `503 Service Unavailable`.
+    ///   The status of each check is responded, the failure reason is logged instead
+    ///
+    /// the State need hold the [Health], for instance a `health: Health` field
+    pub struct HealthRoutes {
+        liveness: &'static str,
+        readiness: &'static str,
+        timeout: Duration,
+    }
+
+    impl Default for HealthRoutes {
+        fn default() -> Self {
+            Self::new("/healthz", "/readyz")
+        }
+    }
+
+    impl HealthRoutes {
+        pub fn new(liveness: &'static str, readiness: &'static str) -> Self {
+            Self {
+                liveness,
+                readiness,
+                timeout: Duration::from_secs(5),
+            }
+        }
+
+        /// the timeout of each health check, default is 5s
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            self.timeout = timeout;
+            self
+        }
+    }
+
+    impl<S> PrepareRouteEffect<S> for HealthRoutes
+    where
+        S: Clone + Send + Sync + 'static,
+        Health: FromRef<S>,
+    {
+        fn set_route(self, route: Router<S>) -> Router<S> {
+            let timeout = self.timeout;
+            route
+                .route(
+                    self.liveness,
+                    get(|| async { Json(json!({ "status": "alive" })) }),
+                )
+                .route(
+                    self.readiness,
+                    get(move |State(health): State<Health>| readiness(health, timeout)),
+                )
+        }
+    }
+
+    #[cfg_attr(not(feature = "logger"), allow(unused_variables))]
+    pub(super) async fn readiness(health: Health, timeout: Duration) -> Response {
+        if !health.is_ready() {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(json!({ "status": "not_ready" })),
+            )
+                .into_response();
+        }
+
+        let checks = health.check(timeout).await;
+        let healthy = checks.iter().all(|check| check.error.is_none());
+        let detail = checks
+            .into_iter()
+            .map(|check| {
+                let status = match check.error {
+                    None => "up",
+                    Some(error) => {
+                        warn!(health.check = check.name, error, "Health Check Failure");
+                        "down"
+                    }
+                };
+                (check.name, json!({ "status": status }))
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let (code, status) = if healthy {
+            (StatusCode::OK, "ready")
+        } else {
+            (StatusCode::SERVICE_UNAVAILABLE, "unhealthy")
+        };
+        (code, Json(json!({ "status": status, "checks": detail }))).into_response()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{io, sync::Arc, time::Duration};
+
+    use super::{AddHealthCheck, Health};
+    use crate::{
+        server_prepare::EmptyDecorator,
+        worker::{Worker, Workers},
+        ConcurrentPrepareSet, SerialPrepareSet,
+    };
+
+    #[tokio::test]
+    async fn test_concurrent_aggregate() {
+        fn check(
+            name: &'static str,
+        ) -> impl FnOnce(Arc<()>) -> futures::future::Ready<Result<AddHealthCheck, io::Error>>
+        {
+            move |_| {
+                futures::future::ok(AddHealthCheck::new(name, || async {
+                    Ok::<_, io::Error>(())
+                }))
+            }
+        }
+        fn worker(
+            name: &'static str,
+        ) -> impl FnOnce(Arc<()>) -> futures::future::Ready<Result<Worker<()>, io::Error>> {
+            move |_| {
+                futures::future::ok(Worker::new(name, |_: (), _| async {
+                    Ok::<_, io::Error>(())
+                }))
+            }
+        }
+
+        let serial = SerialPrepareSet::new(Arc::new(()), EmptyDecorator)
+            .then_state(check("a"))
+            .then_state(worker("a"));
+        let concurrent = ConcurrentPrepareSet::new(serial.get_configure(), &EmptyDecorator)
+            .join_state(check("b"))
+            .join_state(worker("b"));
+        let (mut states, ..) = serial
+            .combine(concurrent)
+            .unwrap()
+            .0
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert!(states.overwritten().is_empty());
+        let health = Health::from_collector(&mut states);
+        assert_eq!(health.check(Duration::from_secs(1)).await.len(), 2);
+        assert_eq!(states.take::<Workers<()>>().unwrap().0.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_health_check() {
+        let health = Health::default();
+        health.register("ok", || async { Ok::<_, io::Error>(()) });
+        health.register("down", || async { Err(io::Error::other("refused")) });
+        health.register("slow", || async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            Ok::<_, io::Error>(())
+        });
+
+        assert!(!health.is_ready());
+        health.set_ready(true);
+        assert!(health.is_ready());
+
+        let checks = health.check(Duration::from_millis(10)).await;
+        assert_eq!(checks[0].error, None);
+        assert_eq!(checks[1].error.as_deref(), Some("refused"));
+        assert!(checks[2].error.is_some());
+    }
+
+    #[cfg(feature = "health")]
+    #[tokio::test]
+    async fn test_readiness() {
+        use axum::body::to_bytes;
+        use http::StatusCode;
+        use serde_json::{json, Value};
+
+        let health = Health::default();
+        health.register("db", || async { Err(io::Error::other("password=secret")) });
+        health.set_ready(true);
+
+        let resp = super::routes::readiness(health, Duration::from_secs(1)).await;
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let body = serde_json::from_slice::<Value>(&body).unwrap();
+        assert_eq!(
+            body,
+            json!({ "status": "unhealthy", "checks": { "db": { "status": "down" } } })
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    any::type_name,
+    error,
+    fmt::{Debug, Formatter},
+    future::Future,
+    ops::Deref,
+    sync::Arc,
+};
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use http::{request::Parts, StatusCode};
+use tokio::sync::OnceCell;
+use tower::layer::util::Identity;
+
+use crate::prepare_behave::{
+    effect_traits::{PrepareMiddlewareEffect, PrepareStateEffect},
+    StateCollector,
+};
+
+type BoxError = Box<dyn error::Error + Send + Sync>;
+type LazyInit<T> = Box<dyn Fn() -> BoxFuture<'static, Result<T, BoxError>> + Send + Sync>;
+
+struct LazyInner<T> {
+    cell: OnceCell<T>,
+    init: LazyInit<T>,
+}
+
+/// the state initialized on the first use, rather than before the server start
+///
+/// - the concurrent callers share the same initialization
+/// - if the initialization failure, it will retry on the next access
+pub struct Lazy<T> {
+    inner: Arc<LazyInner<T>>,
+}
+
+impl<T> Clone for Lazy<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> Debug for Lazy<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Lazy")
+            .field("type", &type_name::<T>())
+            .field("initialized", &self.inner.cell.initialized())
+            .finish()
+    }
+}
+
+impl<T: Send + Sync + 'static> Lazy<T> {
+    pub fn new<F, Fut, E>(init: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        let init: LazyInit<T> = Box::new(move || {
+            let fut = init();
+            Box::pin(async move { fut.await.map_err(Into::into) })
+        });
+        Self {
+            inner: Arc::new(LazyInner {
+                cell: OnceCell::new(),
+                init,
+            }),
+        }
+    }
+
+    /// get the state, initialize it if not yet
+    pub async fn get(&self) -> Result<&T, LazyInitError> {
+        self.inner
+            .cell
+            .get_or_try_init(|| async {
+                debug!(lazy_state = type_name::<T>(), "Initialize Lazy State");
+                (self.inner.init)().await.map_err(|source| {
+                    let err = LazyInitError {
+                        name: type_name::<T>(),
+                        source,
+                    };
+                    warn!(lazy_state = type_name::<T>(), error = %err, "Lazy State Initialize Failure");
+                    err
+                })
+            })
+            .await
+    }
+
+    /// get the state if it has been initialized
+    pub fn try_get(&self) -> Option<&T> {
+        self.inner.cell.get()
+    }
+}
+
+/// extractor of [Lazy] state, await the initialization before the handler
+///
+/// reject with `503 Service Unavailable` if the initialization failure,
+/// the detail is logged instead of responded
+pub struct LazyState<T>(Lazy<T>);
+
+impl<T> Deref for LazyState<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+            .inner
+            .cell
+            .get()
+            .expect("LazyState is extracted after initialized")
+    }
+}
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for LazyState<T>
+where
+    S: Send + Sync,
+    T: Send + Sync + 'static,
+    Lazy<T>: FromRef<S>,
+{
+    type Rejection = LazyInitError;
+
+    async fn from_request_parts(_: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let lazy = Lazy::<T>::from_ref(state);
+        lazy.get().await?;
+        Ok(Self(lazy))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+/// error while initializing the [Lazy] state
+#[error("Lazy State `{name}` Initialize Failure: {source}")]
+pub struct LazyInitError {
+    name: &'static str,
+    #[source]
+    source: BoxError,
+}
+
+impl IntoResponse for LazyInitError {
+    fn into_response(self) -> Response {
+        warn!(lazy.state = self.name, error = %self.source, "Lazy State Unavailable");
+        (StatusCode::SERVICE_UNAVAILABLE, "Service Unavailable").into_response()
+    }
+}
+
+/// [PrepareStateEffect] or [PrepareMiddlewareEffect] adding a [Lazy] state
+///
+/// the server start without waiting for it, the handler can extract it by [LazyState]
+pub struct AddLazyState<T>(pub Lazy<T>);
+
+impl<T: Send + Sync + 'static> AddLazyState<T> {
+    pub fn new<F, Fut, E>(init: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        Self(Lazy::new(init))
+    }
+}
+
+impl<T: 'static, Service> PrepareMiddlewareEffect<Service> for AddLazyState<T> {
+    type Middleware = Identity;
+
+    fn take(self, states: &mut StateCollector) -> Self::Middleware {
+        self.take_state(states);
+        Identity::new()
+    }
+}
+
+impl<T: 'static> PrepareStateEffect for AddLazyState<T> {
+    fn take_state(self, states: &mut StateCollector) {
+        states.insert(self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io,
+        sync::atomic::{AtomicUsize, Ordering},
+        sync::Arc,
+    };
+
+    use axum::{body::to_bytes, response::IntoResponse};
+    use http::StatusCode;
+
+    use super::Lazy;
+
+    #[tokio::test]
+    async fn test_lazy_retry() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        let lazy = Lazy::new(move || {
+            let counter = Arc::clone(&counter);
+            async move {
+                match counter.fetch_add(1, Ordering::SeqCst) {
+                    0 => Err(io::Error::other("not ready")),
+                    n => Ok(n),
+                }
+            }
+        });
+
+        assert!(lazy.try_get().is_none());
+        assert!(lazy.get().await.is_err());
+
+        let other = lazy.clone();
+        let (a, b) = tokio::join!(lazy.get(), other.get());
+        assert_eq!(*a.unwrap(), 1);
+        assert_eq!(*b.unwrap(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_error_response() {
+        let lazy = Lazy::<u8>::new(|| async { Err(io::Error::other("password=secret")) });
+        let resp = lazy.get().await.unwrap_err().into_response();
+        assert_eq!(resp.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body, "Service Unavailable");
+    }
+}
@@ -0,0 +1,585 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock, RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::{header::CONTENT_TYPE, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+
+use crate::{prepare_behave::effect_traits::PrepareRouteEffect, BootReport, PrepareError};
+
+/// the default buckets of latency histogram, in seconds
+pub const DEFAULT_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+    Histogram,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Counter => "counter",
+            Kind::Gauge => "gauge",
+            Kind::Histogram => "histogram",
+        }
+    }
+}
+
+#[derive(Clone)]
+enum Series {
+    Counter(Counter),
+    Gauge(Gauge),
+    Histogram(Histogram),
+}
+
+struct Family {
+    help: String,
+    kind: Kind,
+    series: BTreeMap<Labels, Series>,
+}
+
+/// the registry of metrics, rendered in the Prometheus text exposition format, require `metrics` feature
+///
+/// the builtin metrics of the server are registered in [Metrics::global], the prepares
+/// can register custom metrics there as well, then keep the handle in the State
+///
+/// - `axum_starter_prepare_duration_seconds{prepare}` the duration of each prepare
+/// - `axum_starter_prepare_failures_total{prepare}` the prepare failed
+/// - `axum_starter_boot_duration_seconds` from `preparing` called to the server ready
+/// - `axum_starter_worker_failures_total{worker}` the worker failed
+/// - `axum_starter_http_requests_in_flight` the requests being handled
+/// - `axum_starter_http_request_duration_seconds{method, route, status}` the latency per matched route
+///
+/// the requests are tracked by a middleware in [`Phase::Tracing`](crate::phase::Phase::Tracing),
+/// which is listed in the [BootReport](crate::BootReport)
+#[derive(Clone, Default)]
+pub struct Metrics {
+    families: Arc<Mutex<BTreeMap<String, Family>>>,
+}
+
+impl Metrics {
+    /// the registry shared by the whole process
+    pub fn global() -> &'static Metrics {
+        static GLOBAL: OnceLock<Metrics> = OnceLock::new();
+        GLOBAL.get_or_init(Metrics::default)
+    }
+
+    /// get or register the counter `name` with `labels`
+    ///
+    /// ## Panic
+    /// the `name` has been registered with another type
+    pub fn counter(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Counter {
+        match self.series(name, help, labels, Kind::Counter, || {
+            Series::Counter(Counter::default())
+        }) {
+            Series::Counter(counter) => counter,
+            _ => unreachable!(),
+        }
+    }
+
+    /// get or register the gauge `name` with `labels`
+    ///
+    /// ## Panic
+    /// the `name` has been registered with another type
+    pub fn gauge(&self, name: &str, help: &str, labels: &[(&str, &str)]) -> Gauge {
+        match self.series(name, help, labels, Kind::Gauge, || {
+            Series::Gauge(Gauge::default())
+        }) {
+            Series::Gauge(gauge) => gauge,
+            _ => unreachable!(),
+        }
+    }
+
+    /// get or register the histogram `name` with `labels`, the `buckets` are the upper bounds
+    /// in ascending order, only used when first registered
+    ///
+    /// ## Panic
+    /// the `name` has been registered with another type
+    pub fn histogram(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        buckets: &[f64],
+    ) -> Histogram {
+        match self.series(name, help, labels, Kind::Histogram, || {
+            Series::Histogram(Histogram::new(buckets))
+        }) {
+            Series::Histogram(histogram) => histogram,
+            _ => unreachable!(),
+        }
+    }
+
+    fn series(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[(&str, &str)],
+        kind: Kind,
+        init: impl FnOnce() -> Series,
+    ) -> Series {
+        let mut families = self.families.lock().expect("metrics poisoned");
+        let family = families.entry(name.to_owned()).or_insert_with(|| Family {
+            help: help.to_owned(),
+            kind,
+            series: BTreeMap::new(),
+        });
+        assert_eq!(
+            family.kind,
+            kind,
+            "metric `{name}` has been registered as {}",
+            family.kind.name()
+        );
+        let labels = labels
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        family.series.entry(labels).or_insert_with(init).clone()
+    }
+
+    /// render all metrics in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let families = self.families.lock().expect("metrics poisoned");
+        let mut out = String::new();
+        for (name, family) in families.iter() {
+            let _ = writeln!(out, "# HELP {name} {}", family.help);
+            let _ = writeln!(out, "# TYPE {name} {}", family.kind.name());
+            for (labels, series) in &family.series {
+                match series {
+                    Series::Counter(counter) => {
+                        let _ =
+                            writeln!(out, "{name}{} {}", fmt_labels(labels, None), counter.get());
+                    }
+                    Series::Gauge(gauge) => {
+                        let _ = writeln!(out, "{name}{} {}", fmt_labels(labels, None), gauge.get());
+                    }
+                    Series::Histogram(histogram) => histogram.render(&mut out, name, labels),
+                }
+            }
+        }
+        out
+    }
+}
+
+fn fmt_labels(labels: &Labels, le: Option<&str>) -> String {
+    let pairs = labels
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.as_str()))
+        .chain(le.map(|le| ("le", le)))
+        .map(|(k, v)| {
+            let v = v
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{k}=\"{v}\"")
+        })
+        .collect::<Vec<_>>();
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+/// a monotonically increasing metric
+#[derive(Debug, Clone, Default)]
+pub struct Counter(Arc<AtomicU64>);
+
+impl Counter {
+    pub fn inc(&self) {
+        self.add(1)
+    }
+
+    pub fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// a metric can go up and down
+#[derive(Debug, Clone, Default)]
+pub struct Gauge(Arc<AtomicU64>);
+
+impl Gauge {
+    pub fn set(&self, value: f64) {
+        self.0.store(value.to_bits(), Ordering::Relaxed)
+    }
+
+    pub fn add(&self, delta: f64) {
+        add_f64(&self.0, delta)
+    }
+
+    pub fn inc(&self) {
+        self.add(1.0)
+    }
+
+    pub fn dec(&self) {
+        self.add(-1.0)
+    }
+
+    pub fn get(&self) -> f64 {
+        f64::from_bits(self.0.load(Ordering::Relaxed))
+    }
+}
+
+/// add on the `f64` stored as bits
+fn add_f64(atomic: &AtomicU64, delta: f64) {
+    let _ = atomic.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| {
+        Some((f64::from_bits(bits) + delta).to_bits())
+    });
+}
+
+#[derive(Debug)]
+struct HistogramInner {
+    buckets: Vec<f64>,
+    counts: Vec<AtomicU64>,
+    count: AtomicU64,
+    /// the sum as `f64` bits
+    sum: AtomicU64,
+}
+
+/// the distribution of observed values, like the request latency
+#[derive(Debug, Clone)]
+pub struct Histogram(Arc<HistogramInner>);
+
+impl Histogram {
+    fn new(buckets: &[f64]) -> Self {
+        Self(Arc::new(HistogramInner {
+            buckets: buckets.to_vec(),
+            counts: buckets.iter().map(|_| AtomicU64::new(0)).collect(),
+            count: AtomicU64::new(0),
+            sum: AtomicU64::new(0f64.to_bits()),
+        }))
+    }
+
+    pub fn observe(&self, value: f64) {
+        let inner = &self.0;
+        if let Some(idx) = inner.buckets.iter().position(|bound| value <= *bound) {
+            inner.counts[idx].fetch_add(1, Ordering::Relaxed);
+        }
+        inner.count.fetch_add(1, Ordering::Relaxed);
+        add_f64(&inner.sum, value);
+    }
+
+    pub fn observe_duration(&self, duration: Duration) {
+        self.observe(duration.as_secs_f64())
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &Labels) {
+        let inner = &self.0;
+        let mut cumulative = 0;
+        for (bound, count) in inner.buckets.iter().zip(&inner.counts) {
+            cumulative += count.load(Ordering::Relaxed);
+            let le = bound.to_string();
+            let _ = writeln!(
+                out,
+                "{name}_bucket{} {cumulative}",
+                fmt_labels(labels, Some(&le))
+            );
+        }
+        let count = inner.count.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "{name}_bucket{} {count}",
+            fmt_labels(labels, Some("+Inf"))
+        );
+        let sum = f64::from_bits(inner.sum.load(Ordering::Relaxed));
+        let _ = writeln!(out, "{name}_sum{} {sum}", fmt_labels(labels, None));
+        let _ = writeln!(out, "{name}_count{} {count}", fmt_labels(labels, None));
+    }
+}
+
+/// the builtin metrics recorded during `preparing`
+pub(crate) fn record_boot(report: &BootReport) {
+    let metrics = Metrics::global();
+    for prepare in &report.prepares {
+        metrics
+            .gauge(
+                "axum_starter_prepare_duration_seconds",
+                "the duration of each prepare",
+                &[("prepare", prepare.name)],
+            )
+            .set(prepare.duration.as_secs_f64());
+    }
+    metrics
+        .gauge(
+            "axum_starter_boot_duration_seconds",
+            "from preparing called to the server ready",
+            &[],
+        )
+        .set(report.total.as_secs_f64());
+}
+
+pub(crate) fn record_prepare_failure(err: &PrepareError) {
+    Metrics::global()
+        .counter(
+            "axum_starter_prepare_failures_total",
+            "the prepare failed",
+            &[("prepare", err.prepare())],
+        )
+        .inc()
+}
+
+pub(crate) fn record_worker_failure(worker: &str) {
+    Metrics::global()
+        .counter(
+            "axum_starter_worker_failures_total",
+            "the worker failed",
+            &[("worker", worker)],
+        )
+        .inc()
+}
+
+/// the series of [track_request], resolved once rather than per request
+#[derive(Clone)]
+pub(crate) struct RequestMetrics {
+    registry: Metrics,
+    in_flight: Gauge,
+    latency: Arc<RwLock<Latency>>,
+}
+
+/// the latency histograms, the routes are interned as they are finite
+#[derive(Default)]
+struct Latency {
+    routes: HashSet<&'static str>,
+    histograms: HashMap<(Method, &'static str, StatusCode), Histogram>,
+}
+
+impl RequestMetrics {
+    pub(crate) fn new(metrics: &Metrics) -> Self {
+        Self {
+            registry: metrics.clone(),
+            in_flight: metrics.gauge(
+                "axum_starter_http_requests_in_flight",
+                "the requests being handled",
+                &[],
+            ),
+            latency: Arc::default(),
+        }
+    }
+
+    fn observe(&self, method: &Method, route: &str, status: StatusCode, latency: Duration) {
+        {
+            let read = self.latency.read().expect("metrics poisoned");
+            if let Some(histogram) = read
+                .routes
+                .get(route)
+                .and_then(|route| read.histograms.get(&(method.clone(), *route, status)))
+            {
+                return histogram.observe_duration(latency);
+            }
+        }
+
+        let mut write = self.latency.write().expect("metrics poisoned");
+        let route = match write.routes.get(route) {
+            Some(route) => *route,
+            None => {
+                let route: &'static str = Box::leak(route.into());
+                write.routes.insert(route);
+                route
+            }
+        };
+        write
+            .histograms
+            .entry((method.clone(), route, status))
+            .or_insert_with(|| {
+                self.registry.histogram(
+                    "axum_starter_http_request_duration_seconds",
+                    "the latency of requests per matched route",
+                    &[
+                        ("method", method.as_str()),
+                        ("route", route),
+                        ("status", status.as_str()),
+                    ],
+                    DEFAULT_BUCKETS,
+                )
+            })
+            .observe_duration(latency)
+    }
+}
+
+struct InFlight<'a>(&'a Gauge);
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.dec()
+    }
+}
+
+/// the middleware recording in-flight requests and the latency per matched route
+pub(crate) async fn track_request(
+    State(metrics): State<RequestMetrics>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    let route = request.extensions().get::<MatchedPath>().cloned();
+
+    metrics.in_flight.inc();
+    // the request may be dropped before finished, like client disconnect or timeout
+    let guard = InFlight(&metrics.in_flight);
+    let start = Instant::now();
+    let response = next.run(request).await;
+    drop(guard);
+
+    let route = route.as_ref().map_or("unmatched", MatchedPath::as_str);
+    metrics.observe(&method, route, response.status(), start.elapsed());
+    response
+}
+
+/// [PrepareRouteEffect] serving [Metrics::global] in the Prometheus text exposition format,
+/// require `metrics` feature
+pub struct MetricsRoute(&'static str);
+
+impl MetricsRoute {
+    pub fn new(path: &'static str) -> Self {
+        Self(path)
+    }
+}
+
+impl Default for MetricsRoute {
+    fn default() -> Self {
+        Self::new("/metrics")
+    }
+}
+
+impl<S> PrepareRouteEffect<S> for MetricsRoute
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn set_route(self, route: Router<S>) -> Router<S> {
+        route.route(
+            self.0,
+            get(|| async {
+                (
+                    [(CONTENT_TYPE, "text/plain; version=0.0.4")],
+                    Metrics::global().render(),
+                )
+                    .into_response()
+            }),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use axum::{body::Body, extract::Request, routing::get, Router};
+    use tower::ServiceExt;
+
+    use super::{track_request, Metrics, RequestMetrics};
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::default();
+        metrics
+            .counter("jobs_total", "jobs", &[("queue", "a\"b")])
+            .add(2);
+        metrics.gauge("temperature", "temperature", &[]).set(1.5);
+        let latency = metrics.histogram("latency_seconds", "latency", &[], &[0.1, 1.0]);
+        latency.observe(0.05);
+        latency.observe(0.5);
+        latency.observe(5.0);
+
+        assert_eq!(
+            metrics.render(),
+            "# HELP jobs_total jobs\n\
+             # TYPE jobs_total counter\n\
+             jobs_total{queue=\"a\\\"b\"} 2\n\
+             # HELP latency_seconds latency\n\
+             # TYPE latency_seconds histogram\n\
+             latency_seconds_bucket{le=\"0.1\"} 1\n\
+             latency_seconds_bucket{le=\"1\"} 2\n\
+             latency_seconds_bucket{le=\"+Inf\"} 3\n\
+             latency_seconds_sum 5.55\n\
+             latency_seconds_count 3\n\
+             # HELP temperature temperature\n\
+             # TYPE temperature gauge\n\
+             temperature 1.5\n"
+        );
+    }
+
+    #[test]
+    fn test_histogram_sum() {
+        let metrics = Metrics::default();
+        let sizes = metrics.histogram("size_bytes", "size", &[], &[1024.0]);
+        sizes.observe(1e12);
+        sizes.observe(1e12);
+        sizes.observe(0.25);
+
+        assert!(metrics
+            .render()
+            .contains("size_bytes_sum 2000000000000.25\n"));
+    }
+
+    #[tokio::test]
+    async fn test_in_flight_dropped() {
+        let router = Router::new()
+            .route("/pending", get(futures::future::pending::<()>))
+            .layer(axum::middleware::from_fn_with_state(
+                RequestMetrics::new(Metrics::global()),
+                track_request,
+            ));
+        let request = Request::get("/pending").body(Body::empty()).unwrap();
+
+        // the request is dropped by timeout
+        let res = tokio::time::timeout(Duration::from_millis(10), router.oneshot(request)).await;
+        assert!(res.is_err());
+
+        let in_flight = Metrics::global().gauge(
+            "axum_starter_http_requests_in_flight",
+            "the requests being handled",
+            &[],
+        );
+        assert_eq!(in_flight.get(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_latency_per_route() {
+        let metrics = RequestMetrics::new(Metrics::global());
+        let router = Router::new().route("/latency/:id", get(|| async {})).layer(
+            axum::middleware::from_fn_with_state(metrics.clone(), track_request),
+        );
+        for uri in ["/latency/1", "/latency/2", "/latency/1"] {
+            let request = Request::get(uri).body(Body::empty()).unwrap();
+            router.clone().oneshot(request).await.unwrap();
+        }
+
+        // the histogram is resolved once for the route pattern
+        assert_eq!(metrics.latency.read().unwrap().histograms.len(), 1);
+        assert!(Metrics::global().render().contains(
+            "axum_starter_http_request_duration_seconds_count\
+             {method=\"GET\",route=\"/latency/:id\",status=\"200\"} 3\n"
+        ));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_kind_mismatch() {
+        let metrics = Metrics::default();
+        metrics.counter("value", "value", &[]);
+        metrics.gauge("value", "value", &[]);
+    }
+}
@@ -0,0 +1,449 @@
+use std::{any::Any, time::Duration};
+
+use axum::{
+    extract::DefaultBodyLimit,
+    response::{IntoResponse, Response},
+};
+use http::{HeaderName, HeaderValue, Method, StatusCode};
+use tap::Pipe;
+use tower_http::{
+    catch_panic::CatchPanicLayer,
+    compression::CompressionLayer,
+    cors::{AllowHeaders, AllowMethods, AllowOrigin, CorsLayer},
+    timeout::TimeoutLayer,
+};
+
+use crate::prepare_behave::{effect_traits::PrepareMiddlewareEffect, StateCollector};
+
+/// the invalid middleware config
+#[derive(Debug, thiserror::Error)]
+pub enum MiddlewareConfigError {
+    #[error("invalid CORS origin `{0}`")]
+    Origin(String),
+    #[error("invalid CORS method `{0}`")]
+    Method(String),
+    #[error("invalid CORS header `{0}`")]
+    Header(String),
+    #[error("CORS credentials can not be allowed with wildcard `*`")]
+    CredentialsWithWildcard,
+}
+
+/// the config of [SetCors], the `*` in list allow any
+///
+/// with `serde` feature, the config sections can be deserialized, the missing fields are default.
+/// The durations are seconds like `1.5` or strings like `"500ms"`, `"30s"`, `"2m"`
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct CorsConfig {
+    pub allow_origins: Vec<String>,
+    pub allow_methods: Vec<String>,
+    pub allow_headers: Vec<String>,
+    pub allow_credentials: bool,
+    /// how long the preflight response can be cached
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "crate::effect_utils::duration::option")
+    )]
+    pub max_age: Option<Duration>,
+}
+
+/// [PrepareMiddlewareEffect] applying CORS, require `middleware` feature
+///
+/// ```rust
+/// use axum_starter::{prepare, middleware::{CorsConfig, MiddlewareConfigError, SetCors}};
+///
+/// #[prepare(Cors?)]
+/// fn cors(config: CorsConfig) -> Result<SetCors, MiddlewareConfigError> {
+///     SetCors::new(config)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SetCors(CorsLayer);
+
+impl SetCors {
+    pub fn new(config: CorsConfig) -> Result<Self, MiddlewareConfigError> {
+        let wildcard = |list: &[String]| list.iter().any(|item| item == "*");
+        if config.allow_credentials
+            && (wildcard(&config.allow_origins)
+                || wildcard(&config.allow_methods)
+                || wildcard(&config.allow_headers))
+        {
+            return Err(MiddlewareConfigError::CredentialsWithWildcard);
+        }
+
+        let origins = if wildcard(&config.allow_origins) {
+            AllowOrigin::any()
+        } else {
+            config
+                .allow_origins
+                .iter()
+                .map(|origin| {
+                    HeaderValue::try_from(origin)
+                        .map_err(|_| MiddlewareConfigError::Origin(origin.clone()))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .pipe(AllowOrigin::list)
+        };
+        let methods = if wildcard(&config.allow_methods) {
+            AllowMethods::any()
+        } else {
+            config
+                .allow_methods
+                .iter()
+                .map(|method| {
+                    method
+                        .to_ascii_uppercase()
+                        .parse::<Method>()
+                        .map_err(|_| MiddlewareConfigError::Method(method.clone()))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .pipe(AllowMethods::list)
+        };
+        let headers = if wildcard(&config.allow_headers) {
+            AllowHeaders::any()
+        } else {
+            config
+                .allow_headers
+                .iter()
+                .map(|header| {
+                    header
+                        .parse::<HeaderName>()
+                        .map_err(|_| MiddlewareConfigError::Header(header.clone()))
+                })
+                .collect::<Result<Vec<_>, _>>()?
+                .pipe(AllowHeaders::list)
+        };
+
+        let mut layer = CorsLayer::new()
+            .allow_origin(origins)
+            .allow_methods(methods)
+            .allow_headers(headers)
+            .allow_credentials(config.allow_credentials);
+        if let Some(max_age) = config.max_age {
+            layer = layer.max_age(max_age);
+        }
+        Ok(Self(layer))
+    }
+}
+
+impl<S: 'static> PrepareMiddlewareEffect<S> for SetCors {
+    type Middleware = CorsLayer;
+
+    fn take(self, _: &mut StateCollector) -> Self::Middleware {
+        self.0
+    }
+}
+
+/// the config of [SetCompression], all algorithms are enabled by default
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct CompressionConfig {
+    pub gzip: bool,
+    pub deflate: bool,
+    pub br: bool,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            gzip: true,
+            deflate: true,
+            br: true,
+        }
+    }
+}
+
+/// [PrepareMiddlewareEffect] compressing the response body by the `Accept-Encoding`,
+/// require `middleware` feature
+#[derive(Debug, Clone, Default)]
+pub struct SetCompression(CompressionConfig);
+
+impl SetCompression {
+    pub fn new(config: CompressionConfig) -> Self {
+        Self(config)
+    }
+}
+
+impl<S: 'static> PrepareMiddlewareEffect<S> for SetCompression {
+    type Middleware = CompressionLayer;
+
+    fn take(self, _: &mut StateCollector) -> Self::Middleware {
+        CompressionLayer::new()
+            .gzip(self.0.gzip)
+            .deflate(self.0.deflate)
+            .br(self.0.br)
+    }
+}
+
+/// the config of [SetBodyLimit]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct BodyLimitConfig {
+    /// the max bytes of request body, default is 2MB
+    pub limit: usize,
+}
+
+impl Default for BodyLimitConfig {
+    fn default() -> Self {
+        Self {
+            limit: 2 * 1024 * 1024,
+        }
+    }
+}
+
+/// [PrepareMiddlewareEffect] limiting the request body size, the extractors reading the body
+/// respond `413 Payload Too Large` if exceeded, require `middleware` feature
+#[derive(Debug, Clone, Default)]
+pub struct SetBodyLimit(BodyLimitConfig);
+
+impl SetBodyLimit {
+    pub fn new(config: BodyLimitConfig) -> Self {
+        Self(config)
+    }
+}
+
+impl<S: 'static> PrepareMiddlewareEffect<S> for SetBodyLimit {
+    type Middleware = DefaultBodyLimit;
+
+    fn take(self, _: &mut StateCollector) -> Self::Middleware {
+        DefaultBodyLimit::max(self.0.limit)
+    }
+}
+
+/// the config of [SetTimeout]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct TimeoutConfig {
+    /// default is 30s
+    #[cfg_attr(feature = "serde", serde(with = "crate::effect_utils::duration"))]
+    pub timeout: Duration,
+}
+
+impl Default for TimeoutConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// [PrepareMiddlewareEffect] responding `408 Request Timeout` if the request take too long,
+/// require `middleware` feature
+#[derive(Debug, Clone, Default)]
+pub struct SetTimeout(TimeoutConfig);
+
+impl SetTimeout {
+    pub fn new(config: TimeoutConfig) -> Self {
+        Self(config)
+    }
+}
+
+impl<S: 'static> PrepareMiddlewareEffect<S> for SetTimeout {
+    type Middleware = TimeoutLayer;
+
+    fn take(self, _: &mut StateCollector) -> Self::Middleware {
+        TimeoutLayer::new(self.0.timeout)
+    }
+}
+
+/// the config of [CatchPanic]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct CatchPanicConfig {
+    /// respond the panic message rather than a plain `500 Internal Server Error`,
+    /// only for development
+    pub expose_message: bool,
+}
+
+type PanicHandler = fn(Box<dyn Any + Send + 'static>) -> Response;
+
+/// [PrepareMiddlewareEffect] converting the panic in handlers into `500 Internal Server Error`,
+/// rather than dropping the connection, require `middleware` feature
+#[derive(Debug, Clone, Default)]
+pub struct CatchPanic(CatchPanicConfig);
+
+impl CatchPanic {
+    pub fn new(config: CatchPanicConfig) -> Self {
+        Self(config)
+    }
+}
+
+fn panic_message<'a>(err: &'a (dyn Any + Send + 'static)) -> &'a str {
+    err.downcast_ref::<&str>()
+        .copied()
+        .or_else(|| err.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+#[cfg_attr(not(feature = "logger"), allow(unused_variables))]
+fn hide_panic(err: Box<dyn Any + Send + 'static>) -> Response {
+    warn!(panic = panic_message(&*err), "Handler Panicked");
+    StatusCode::INTERNAL_SERVER_ERROR.into_response()
+}
+
+fn expose_panic(err: Box<dyn Any + Send + 'static>) -> Response {
+    let message = panic_message(&*err).to_owned();
+    warn!(panic = message, "Handler Panicked");
+    (StatusCode::INTERNAL_SERVER_ERROR, message).into_response()
+}
+
+impl<S: 'static> PrepareMiddlewareEffect<S> for CatchPanic {
+    type Middleware = CatchPanicLayer<PanicHandler>;
+
+    fn take(self, _: &mut StateCollector) -> Self::Middleware {
+        let handler: PanicHandler = if self.0.expose_message {
+            expose_panic
+        } else {
+            hide_panic
+        };
+        CatchPanicLayer::custom(handler)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{
+        body::{to_bytes, Body},
+        routing::get,
+        Router,
+    };
+    use http::{Request, StatusCode};
+    use tower::Service;
+
+    use std::time::Duration;
+
+    use http::header::{ACCEPT_ENCODING, CONTENT_ENCODING};
+
+    use super::{
+        BodyLimitConfig, CatchPanic, CatchPanicConfig, CompressionConfig, CorsConfig,
+        MiddlewareConfigError, SetBodyLimit, SetCompression, SetCors, SetTimeout, TimeoutConfig,
+    };
+    use crate::{PrepareMiddlewareEffect, StateCollector};
+
+    #[test]
+    fn test_cors_credentials_with_wildcard() {
+        let config = CorsConfig {
+            allow_origins: vec!["*".into()],
+            allow_credentials: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            SetCors::new(config),
+            Err(MiddlewareConfigError::CredentialsWithWildcard)
+        ));
+    }
+
+    async fn boom() -> &'static str {
+        panic!("boom")
+    }
+
+    #[tokio::test]
+    async fn test_catch_panic() {
+        let layer = PrepareMiddlewareEffect::<()>::take(
+            CatchPanic::new(CatchPanicConfig {
+                expose_message: true,
+            }),
+            &mut StateCollector::new(),
+        );
+        let mut router = Router::<()>::new().route("/", get(boom)).layer(layer);
+
+        let resp = router.call(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"boom");
+    }
+
+    #[tokio::test]
+    async fn test_compression() {
+        let mut router = Router::<()>::new()
+            .route("/", get(|| async { "a".repeat(1024) }))
+            .layer(PrepareMiddlewareEffect::<()>::take(
+                SetCompression::new(CompressionConfig {
+                    gzip: true,
+                    deflate: false,
+                    br: false,
+                }),
+                &mut StateCollector::new(),
+            ));
+
+        let request = Request::get("/")
+            .header(ACCEPT_ENCODING, "br, gzip")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router.call(request).await.unwrap();
+        assert_eq!(resp.headers()[CONTENT_ENCODING], "gzip");
+    }
+
+    #[tokio::test]
+    async fn test_body_limit() {
+        let mut router = Router::<()>::new()
+            .route("/", axum::routing::post(|body: String| async move { body }))
+            .layer(PrepareMiddlewareEffect::<()>::take(
+                SetBodyLimit::new(BodyLimitConfig { limit: 8 }),
+                &mut StateCollector::new(),
+            ));
+
+        let request = |body: &'static str| Request::post("/").body(Body::from(body)).unwrap();
+        let resp = router.call(request("12345678")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        let resp = router.call(request("123456789")).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timeout() {
+        let mut router = Router::<()>::new()
+            .route(
+                "/",
+                get(|| async { tokio::time::sleep(Duration::from_secs(60)).await }),
+            )
+            .layer(PrepareMiddlewareEffect::<()>::take(
+                SetTimeout::new(TimeoutConfig {
+                    timeout: Duration::from_secs(1),
+                }),
+                &mut StateCollector::new(),
+            ));
+
+        let resp = router.call(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::REQUEST_TIMEOUT);
+    }
+
+    #[cfg(all(feature = "serde", feature = "serde_json"))]
+    #[test]
+    fn test_deserialize() {
+        use serde_json::{from_value, json};
+
+        let cors = from_value::<CorsConfig>(json!({
+            "allow_origins": ["https://example.com"],
+            "max_age": "2m",
+        }))
+        .unwrap();
+        assert_eq!(cors.allow_origins, ["https://example.com"]);
+        assert!(!cors.allow_credentials);
+        assert_eq!(cors.max_age, Some(Duration::from_secs(120)));
+
+        let timeout = from_value::<TimeoutConfig>(json!({ "timeout": 1.5 })).unwrap();
+        assert_eq!(timeout.timeout, Duration::from_millis(1500));
+        let timeout = from_value::<TimeoutConfig>(json!({ "timeout": "500ms" })).unwrap();
+        assert_eq!(timeout.timeout, Duration::from_millis(500));
+        assert!(from_value::<TimeoutConfig>(json!({ "timeout": "5d" })).is_err());
+        assert_eq!(
+            from_value::<TimeoutConfig>(json!({})).unwrap().timeout,
+            Duration::from_secs(30)
+        );
+
+        let compression = from_value::<CompressionConfig>(json!({ "br": false })).unwrap();
+        assert!(compression.gzip && !compression.br);
+        assert_eq!(
+            from_value::<BodyLimitConfig>(json!({ "limit": 1024 }))
+                .unwrap()
+                .limit,
+            1024
+        );
+        assert!(
+            from_value::<CatchPanicConfig>(json!({ "expose_message": true }))
+                .unwrap()
+                .expose_message
+        );
+    }
+}
@@ -1,5 +1,29 @@
+#[cfg(feature = "debug-state")]
+pub(crate) mod debug_state;
+/// deserialize the [Duration](std::time::Duration) from the seconds like `1.5`, or the string with unit
+/// `ms`, `s`, `m` or `h` like `"500ms"`
+#[cfg(feature = "serde")]
+pub(crate) mod duration;
+/// help types for the liveness and readiness of the server
+pub mod health;
+mod lazy;
+/// the metrics of boot and runtime
+#[cfg(feature = "metrics")]
+pub mod metrics;
+/// the common middleware built from config
+#[cfg(feature = "middleware")]
+pub mod middleware;
+/// help types for ordering the middleware
+pub mod phase;
+/// help types for limiting the request rate
+pub mod rate_limit;
+/// help types for the request id and correlation
+pub mod request_id;
 /// help types for apply effect on State
 pub mod state;
 
 /// help types for apply effect on [Router](axum::Router)
 pub mod router;
+
+/// help types for running background tasks alongside the server
+pub mod worker;
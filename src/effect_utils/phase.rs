@@ -0,0 +1,458 @@
+use std::{
+    any::type_name,
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use axum::{
+    body::{Body, Bytes},
+    response::{IntoResponse, Response},
+    routing::Route,
+    BoxError, Router,
+};
+use futures::future::Either;
+use http::Request;
+use tower::{
+    layer::util::Identity,
+    util::{BoxCloneService, ServiceExt},
+    Layer, Service, ServiceBuilder,
+};
+
+use crate::prepare_behave::{
+    effect_collectors::state_collector::Aggregate, effect_traits::PrepareMiddlewareEffect,
+    StateCollector,
+};
+
+/// the type erased service wrapped by the [Phased] middleware
+pub type BoxRoute = BoxCloneService<Request<Body>, Response, Infallible>;
+
+type LayerFn = Arc<dyn Fn(BoxRoute) -> BoxRoute + Send + Sync>;
+
+/// where the middleware is in the middleware stack, from the outermost to the innermost
+///
+/// the middleware without phase, added by `layer` or `prepare_middleware`, is in [Phase::Default]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+    /// before all other middleware, like catching panic
+    Outermost,
+    /// observing the whole request, like tracing, request id and metrics
+    Tracing,
+    /// rejecting the request, like authentication and rate limiting
+    Auth,
+    #[default]
+    Default,
+    /// just before the handler, like timeout and body limit
+    Innermost,
+}
+
+/// the middleware in the final order, outermost first
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayerInfo {
+    /// the type name of the layer
+    pub name: &'static str,
+    /// the type name of the prepare adding it, [None] if added by `layer` or built-in
+    pub prepare: Option<&'static str>,
+    pub phase: Phase,
+}
+
+/// [PrepareMiddlewareEffect] putting the middleware of another [PrepareMiddlewareEffect] into the [Phase],
+/// thus its position does not depend on the order of the prepares
+///
+/// in the same phase, the middleware added earlier is outer,
+/// and the phased middleware is outer than the ones without phase in [Phase::Default]
+///
+/// ```rust
+/// use axum_starter::{
+///     phase::{Phase, Phased},
+///     prepare,
+///     request_id::SetRequestId,
+/// };
+///
+/// #[prepare(RequestId)]
+/// fn request_id() -> Phased<SetRequestId> {
+///     Phased::new(Phase::Tracing, SetRequestId::default())
+/// }
+/// ```
+pub struct Phased<E> {
+    phase: Phase,
+    effect: E,
+}
+
+impl<E> Phased<E> {
+    pub fn new(phase: Phase, effect: E) -> Self {
+        Self { phase, effect }
+    }
+}
+
+impl<S, E, ResBody> PrepareMiddlewareEffect<S> for Phased<E>
+where
+    E: PrepareMiddlewareEffect<BoxRoute>,
+    E::Middleware: Send + Sync,
+    <E::Middleware as Layer<BoxRoute>>::Service: Service<Request<Body>, Response = Response<ResBody>, Error = Infallible>
+        + Clone
+        + Send
+        + 'static,
+    <<E::Middleware as Layer<BoxRoute>>::Service as Service<Request<Body>>>::Future: Send + 'static,
+    ResBody: 'static,
+    Response<ResBody>: IntoResponse,
+{
+    type Middleware = Identity;
+
+    fn take(self, states: &mut StateCollector) -> Self::Middleware {
+        let layer = self.effect.take(states);
+        let layer: LayerFn = Arc::new(move |inner: BoxRoute| {
+            BoxCloneService::new(layer.layer(inner).map_response(IntoResponse::into_response))
+        });
+        MiddlewareOrder::push(
+            states,
+            LayerEntry {
+                name: type_name::<E::Middleware>(),
+                prepare: states.producer(),
+                phase: self.phase,
+                layer: Some(layer),
+            },
+        );
+        Identity::new()
+    }
+}
+
+#[derive(Clone)]
+struct LayerEntry {
+    name: &'static str,
+    prepare: Option<&'static str>,
+    phase: Phase,
+    /// [None] for the middleware without phase, it is in the middleware stack
+    layer: Option<LayerFn>,
+}
+
+/// all middleware in added order, in the [StateCollector] during prepare
+#[derive(Clone, Default)]
+pub(crate) struct MiddlewareOrder(Vec<LayerEntry>);
+
+impl MiddlewareOrder {
+    fn push(collector: &mut StateCollector, entry: LayerEntry) {
+        debug!(
+            middleware.layer = entry.name,
+            middleware.phase = ?entry.phase,
+            "Adding Middleware"
+        );
+        collector.aggregate::<Self>().0.push(entry);
+    }
+
+    /// adding the built-in middleware after all prepares, like the metrics
+    #[cfg_attr(
+        not(any(feature = "metrics", feature = "debug-state")),
+        allow(dead_code)
+    )]
+    pub(crate) fn builtin<M>(&mut self, name: &'static str, phase: Phase, layer: M)
+    where
+        M: Layer<BoxRoute> + Send + Sync + 'static,
+        M::Service: Service<Request<Body>, Response = Response, Error = Infallible>
+            + Clone
+            + Send
+            + 'static,
+        <M::Service as Service<Request<Body>>>::Future: Send + 'static,
+    {
+        debug!(middleware.layer = name, middleware.phase = ?phase, "Adding Middleware");
+        self.0.push(LayerEntry {
+            name,
+            prepare: None,
+            phase,
+            layer: Some(Arc::new(move |inner| {
+                BoxCloneService::new(layer.layer(inner))
+            })),
+        });
+    }
+
+    /// record the middleware without phase
+    pub(crate) fn record(collector: &mut StateCollector, name: &'static str) {
+        Self::push(
+            collector,
+            LayerEntry {
+                name,
+                prepare: collector.producer(),
+                phase: Phase::Default,
+                layer: None,
+            },
+        );
+    }
+
+    pub(crate) fn take(collector: &mut StateCollector) -> Self {
+        collector.take().unwrap_or_default()
+    }
+
+    /// take the middleware then adding the built-in ones, the same in `preparing` and `preparing_test`
+    #[cfg_attr(
+        not(any(feature = "metrics", feature = "debug-state")),
+        allow(unused_mut)
+    )]
+    pub(crate) fn take_with_builtin(collector: &mut StateCollector) -> Self {
+        let mut order = Self::take(collector);
+        // the latency per matched route, include the middleware of inner phases
+        #[cfg(feature = "metrics")]
+        order.builtin(
+            "axum_starter::metrics::track_request",
+            Phase::Tracing,
+            axum::middleware::from_fn_with_state(
+                crate::metrics::RequestMetrics::new(crate::metrics::Metrics::global()),
+                crate::metrics::track_request,
+            ),
+        );
+        // the inventory for `DebugState`, after the order taken thus not in the inventory
+        #[cfg(feature = "debug-state")]
+        crate::effect_utils::debug_state::add_inventory(&mut order, collector);
+        order
+    }
+
+    /// the entries outermost first
+    fn sorted(&self) -> Vec<&LayerEntry> {
+        let mut entries = self.0.iter().collect::<Vec<_>>();
+        // stable, thus the earlier one is outer in the same phase
+        entries.sort_by_key(|entry| (entry.phase, entry.layer.is_none()));
+        entries
+    }
+
+    /// the final order of middleware, outermost first
+    pub(crate) fn layers(&self) -> Vec<LayerInfo> {
+        self.sorted()
+            .into_iter()
+            .map(|entry| LayerInfo {
+                name: entry.name,
+                prepare: entry.prepare,
+                phase: entry.phase,
+            })
+            .collect()
+    }
+
+    /// the phased middleware (outer, inner) than the middleware stack
+    pub(crate) fn into_layers(self) -> (PhasedLayer, PhasedLayer) {
+        let mut outer = Vec::new();
+        let mut inner = Vec::new();
+        for entry in self.sorted() {
+            match &entry.layer {
+                Some(layer) if entry.phase == Phase::Innermost => inner.push(Arc::clone(layer)),
+                Some(layer) => outer.push(Arc::clone(layer)),
+                None => {}
+            }
+        }
+        (PhasedLayer(outer.into()), PhasedLayer(inner.into()))
+    }
+}
+
+impl Aggregate for MiddlewareOrder {
+    fn merge(&mut self, other: Self) {
+        self.0.extend(other.0)
+    }
+}
+
+/// the [Layer] applying the phased middleware, outermost first
+#[derive(Clone)]
+pub(crate) struct PhasedLayer(Arc<[LayerFn]>);
+
+impl<S> Layer<S> for PhasedLayer
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Service = PhasedService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        // not boxing without phased middleware
+        if self.0.is_empty() {
+            return PhasedService(PhasedInner::Plain(inner));
+        }
+        PhasedService(PhasedInner::Boxed(
+            self.0
+                .iter()
+                .rev()
+                .fold(BoxCloneService::new(inner), |service, layer| layer(service)),
+        ))
+    }
+}
+
+/// the service applying the [`Phase::Innermost`] middleware around the handler,
+/// wrapped by the middleware stack in test, see [`ServerPrepare::preparing_test`](crate::ServerPrepare::preparing_test)
+#[derive(Clone)]
+pub struct PhasedService<S>(PhasedInner<S>);
+
+#[derive(Clone)]
+enum PhasedInner<S> {
+    Plain(S),
+    Boxed(BoxRoute),
+}
+
+impl<S> Service<Request<Body>> for PhasedService<S>
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible>,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Either<S::Future, <BoxRoute as Service<Request<Body>>>::Future>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match &mut self.0 {
+            PhasedInner::Plain(service) => service.poll_ready(cx),
+            PhasedInner::Boxed(service) => service.poll_ready(cx),
+        }
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        match &mut self.0 {
+            PhasedInner::Plain(service) => Either::Left(service.call(req)),
+            PhasedInner::Boxed(service) => Either::Right(service.call(req)),
+        }
+    }
+}
+
+/// adding the middleware to the router, the phased ones around the middleware stack
+pub(crate) fn layer_router<S, M>(
+    router: Router<S>,
+    middleware: M,
+    (outer, inner): (PhasedLayer, PhasedLayer),
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    M: Layer<Route> + Clone + Send + 'static,
+    M::Service: Service<Request<Body>, Error = Infallible> + Clone + Send + 'static,
+    <M::Service as Service<Request<Body>>>::Response: IntoResponse + 'static,
+    <M::Service as Service<Request<Body>>>::Future: Send + 'static,
+{
+    router.layer(inner).layer(middleware).layer(outer)
+}
+
+/// adding the middleware to the service in test, the same order as [layer_router]
+#[cfg_attr(not(feature = "test-utils"), allow(dead_code))]
+pub(crate) fn layer_service<S, M, B>(
+    service: S,
+    middleware: M,
+    (outer, inner): (PhasedLayer, PhasedLayer),
+) -> impl Service<Request<Body>, Response = Response, Error = Infallible>
+where
+    S: Service<Request<Body>, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    M: Layer<PhasedService<S>>,
+    M::Service:
+        Service<Request<Body>, Response = Response<B>, Error = Infallible> + Clone + Send + 'static,
+    <M::Service as Service<Request<Body>>>::Future: Send + 'static,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    ServiceBuilder::new()
+        .layer(outer)
+        .map_response(IntoResponse::into_response)
+        .layer(middleware)
+        .layer(inner)
+        .service(service)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::{Arc, Mutex};
+
+    use axum::{body::Body, routing::get, Router};
+    use http::Request;
+    use tower::{util::MapRequestLayer, ServiceBuilder, ServiceExt};
+
+    use super::{layer_router, layer_service, LayerEntry, MiddlewareOrder, Phase};
+    use crate::StateCollector;
+
+    #[test]
+    fn test_middleware_order() {
+        let entry = |name, phase, phased: bool| LayerEntry {
+            name,
+            prepare: None,
+            phase,
+            layer: phased.then(|| -> super::LayerFn { std::sync::Arc::new(|route| route) }),
+        };
+        let order = MiddlewareOrder(vec![
+            entry("Timeout", Phase::Innermost, true),
+            entry("Compression", Phase::Default, false),
+            entry("Auth", Phase::Auth, true),
+            entry("Cors", Phase::Default, true),
+            entry("Trace", Phase::Tracing, true),
+            entry("CatchPanic", Phase::Outermost, true),
+        ]);
+
+        let names = order
+            .layers()
+            .into_iter()
+            .map(|layer| layer.name)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "CatchPanic",
+                "Trace",
+                "Auth",
+                "Cors",
+                "Compression",
+                "Timeout"
+            ]
+        );
+        let (outer, inner) = order.into_layers();
+        assert_eq!((outer.0.len(), inner.0.len()), (4, 1));
+    }
+
+    #[test]
+    fn test_builtin() {
+        let mut collector = StateCollector::new();
+        MiddlewareOrder::record(&mut collector, "Compression");
+
+        let mut order = MiddlewareOrder::take(&mut collector);
+        order.builtin(
+            "Metrics",
+            Phase::Tracing,
+            tower::layer::util::Identity::new(),
+        );
+        let layers = order.layers();
+        assert_eq!(
+            layers
+                .iter()
+                .map(|layer| (layer.name, layer.prepare))
+                .collect::<Vec<_>>(),
+            [("Metrics", None), ("Compression", None)]
+        );
+        let (outer, inner) = order.into_layers();
+        assert_eq!((outer.0.len(), inner.0.len()), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_same_order() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let record = |name: &'static str| {
+            let calls = Arc::clone(&calls);
+            MapRequestLayer::new(move |req: Request<Body>| {
+                calls.lock().unwrap().push(name);
+                req
+            })
+        };
+        let layers = || {
+            let mut order = MiddlewareOrder::default();
+            order.builtin("Timeout", Phase::Innermost, record("Timeout"));
+            order.builtin("Auth", Phase::Auth, record("Auth"));
+            order.builtin("CatchPanic", Phase::Outermost, record("CatchPanic"));
+            order.into_layers()
+        };
+        let middleware = ServiceBuilder::new().layer(record("Compression"));
+        let expect = ["CatchPanic", "Auth", "Compression", "Timeout"];
+
+        // preparing
+        let router = layer_router(
+            Router::new().route("/", get(|| async {})),
+            middleware.clone(),
+            layers(),
+        );
+        router.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(std::mem::take(&mut *calls.lock().unwrap()), expect);
+
+        // preparing_test
+        let service = layer_service(
+            tower::service_fn(|_| async { Ok(axum::response::Response::new(Body::empty())) }),
+            middleware,
+            layers(),
+        );
+        service.oneshot(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(*calls.lock().unwrap(), expect);
+    }
+}
@@ -0,0 +1,779 @@
+use std::{
+    collections::HashMap,
+    error,
+    fmt::{Debug, Formatter},
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use axum::{
+    extract::{ConnectInfo, MatchedPath},
+    response::{IntoResponse, Response},
+};
+use futures::future::BoxFuture;
+use http::{header::RETRY_AFTER, request::Parts, HeaderName, Request, StatusCode};
+use tower::{Layer, Service};
+
+use crate::prepare_behave::{effect_traits::PrepareMiddlewareEffect, StateCollector};
+
+type BoxError = Box<dyn error::Error + Send + Sync>;
+type KeyFn = Arc<dyn Fn(&Parts) -> Option<String> + Send + Sync>;
+
+/// the result of acquiring a permit from [RateLimitStore]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Allowed { remaining: u32 },
+    Limited { retry_after: Duration },
+}
+
+impl Decision {
+    /// the decision without counting, `limit` or `period` is zero
+    fn trivial(limit: u32, period: Duration) -> Option<Self> {
+        if limit == 0 {
+            Some(Self::Limited {
+                retry_after: period,
+            })
+        } else if period.is_zero() {
+            Some(Self::Allowed { remaining: limit })
+        } else {
+            None
+        }
+    }
+}
+
+/// where the rate limit counters are stored
+///
+/// the in-memory [TokenBucketStore] and [SlidingWindowStore] are used by default,
+/// a shared store like Redis can be supplied by a prepare adding [RateLimitBackend] into the state
+pub trait RateLimitStore: Send + Sync + 'static {
+    /// acquire a permit for `key`, which has at most `limit` permits each `period`
+    fn acquire<'a>(
+        &'a self,
+        key: &'a str,
+        limit: u32,
+        period: Duration,
+    ) -> BoxFuture<'a, Result<Decision, BoxError>>;
+}
+
+/// the [RateLimitStore] used by [SetRateLimit], in the [StateCollector]
+///
+/// adding it by `AddState::new(RateLimitBackend::new(store))` in a prepare executed before [SetRateLimit]
+#[derive(Clone)]
+pub struct RateLimitBackend(Arc<dyn RateLimitStore>);
+
+impl RateLimitBackend {
+    pub fn new(store: impl RateLimitStore) -> Self {
+        Self(Arc::new(store))
+    }
+}
+
+/// the counters in memory, the idle ones are swept periodically
+struct Entries<T> {
+    entries: HashMap<String, (T, Instant)>,
+    last_sweep: Instant,
+}
+
+impl<T> Default for Entries<T> {
+    fn default() -> Self {
+        Self {
+            entries: HashMap::new(),
+            last_sweep: Instant::now(),
+        }
+    }
+}
+
+impl<T> Entries<T> {
+    const SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+
+    /// the counter of `key`, which can be removed after idle for `idle`
+    fn entry(&mut self, key: &str, idle: Duration, init: impl FnOnce() -> T) -> &mut T {
+        let now = Instant::now();
+        if now.duration_since(self.last_sweep) > Self::SWEEP_INTERVAL {
+            self.entries.retain(|_, (_, expire)| *expire > now);
+            self.last_sweep = now;
+        }
+        let (value, expire) = self
+            .entries
+            .entry(key.to_owned())
+            .or_insert_with(|| (init(), now));
+        *expire = now + idle;
+        value
+    }
+}
+
+/// in-memory [RateLimitStore] by token bucket, allowing burst up to `limit`
+#[derive(Default)]
+pub struct TokenBucketStore(Mutex<Entries<(f64, Instant)>>);
+
+impl TokenBucketStore {
+    fn take(&self, key: &str, limit: u32, period: Duration) -> Decision {
+        if let Some(decision) = Decision::trivial(limit, period) {
+            return decision;
+        }
+        let now = Instant::now();
+        let limit = f64::from(limit);
+        let rate = limit / period.as_secs_f64();
+        let mut entries = self.0.lock().expect("rate limit store poisoned");
+        let (tokens, last) = entries.entry(key, period, || (limit, now));
+
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * rate).min(limit);
+        *last = now;
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Decision::Allowed {
+                remaining: *tokens as u32,
+            }
+        } else {
+            Decision::Limited {
+                retry_after: Duration::try_from_secs_f64((1.0 - *tokens) / rate).unwrap_or(period),
+            }
+        }
+    }
+}
+
+impl RateLimitStore for TokenBucketStore {
+    fn acquire<'a>(
+        &'a self,
+        key: &'a str,
+        limit: u32,
+        period: Duration,
+    ) -> BoxFuture<'a, Result<Decision, BoxError>> {
+        Box::pin(futures::future::ok(self.take(key, limit, period)))
+    }
+}
+
+struct Window {
+    start: Instant,
+    previous: u32,
+    current: u32,
+}
+
+/// in-memory [RateLimitStore] by sliding window, weighting the count of previous window
+#[derive(Default)]
+pub struct SlidingWindowStore(Mutex<Entries<Window>>);
+
+impl SlidingWindowStore {
+    fn take(&self, key: &str, limit: u32, period: Duration) -> Decision {
+        if let Some(decision) = Decision::trivial(limit, period) {
+            return decision;
+        }
+        let now = Instant::now();
+        let mut entries = self.0.lock().expect("rate limit store poisoned");
+        let window = entries.entry(key, period * 2, || Window {
+            start: now,
+            previous: 0,
+            current: 0,
+        });
+
+        let elapsed = now.duration_since(window.start);
+        if elapsed >= period * 2 {
+            *window = Window {
+                start: now,
+                previous: 0,
+                current: 0,
+            };
+        } else if elapsed >= period {
+            window.start += period;
+            window.previous = window.current;
+            window.current = 0;
+        }
+
+        let elapsed = now.duration_since(window.start);
+        let weight = 1.0 - elapsed.as_secs_f64() / period.as_secs_f64();
+        let estimated = f64::from(window.previous) * weight + f64::from(window.current);
+        if estimated + 1.0 <= f64::from(limit) {
+            window.current += 1;
+            Decision::Allowed {
+                remaining: (f64::from(limit) - estimated - 1.0) as u32,
+            }
+        } else {
+            let retry_after = if window.current >= limit {
+                // until the current window become the previous one
+                period - elapsed
+            } else {
+                // until the weight of previous window low enough
+                let weight = f64::from(limit - window.current - 1) / f64::from(window.previous);
+                Duration::try_from_secs_f64(period.as_secs_f64() * (1.0 - weight))
+                    .unwrap_or(period)
+                    .saturating_sub(elapsed)
+            };
+            Decision::Limited { retry_after }
+        }
+    }
+}
+
+impl RateLimitStore for SlidingWindowStore {
+    fn acquire<'a>(
+        &'a self,
+        key: &'a str,
+        limit: u32,
+        period: Duration,
+    ) -> BoxFuture<'a, Result<Decision, BoxError>> {
+        Box::pin(futures::future::ok(self.take(key, limit, period)))
+    }
+}
+
+/// how to identify the client of a request
+///
+/// with `serde` feature, it is deserialized from `"client_ip"` or `{ "header": "x-api-key" }`,
+/// [RateLimitKey::Custom] can only be built in code
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum RateLimitKey {
+    /// the peer address from [ConnectInfo]. If the peer is one of [RateLimitConfig::trusted_proxies],
+    /// the rightmost address in `x-forwarded-for` not of the trusted proxies.
+    /// The request is not limited without [ConnectInfo]
+    #[default]
+    ClientIp,
+    /// the value of the header, like an api key
+    #[cfg_attr(feature = "serde", serde(deserialize_with = "header_name"))]
+    Header(HeaderName),
+    /// extracted from the request, like the user inserted into extensions by auth middleware.
+    /// The request is not limited if [None]
+    #[cfg_attr(feature = "serde", serde(skip))]
+    Custom(KeyFn),
+}
+
+#[cfg(feature = "serde")]
+fn header_name<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<HeaderName, D::Error> {
+    use serde::{de::Error, Deserialize};
+
+    let name = String::deserialize(deserializer)?;
+    HeaderName::try_from(name).map_err(D::Error::custom)
+}
+
+impl Debug for RateLimitKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RateLimitKey::ClientIp => f.write_str("ClientIp"),
+            RateLimitKey::Header(header) => f.debug_tuple("Header").field(header).finish(),
+            RateLimitKey::Custom(_) => f.write_str("Custom"),
+        }
+    }
+}
+
+impl RateLimitKey {
+    pub fn custom<F>(extract: F) -> Self
+    where
+        F: Fn(&Parts) -> Option<String> + Send + Sync + 'static,
+    {
+        Self::Custom(Arc::new(extract))
+    }
+
+    fn extract(&self, parts: &Parts, trusted_proxies: &[IpAddr]) -> Option<String> {
+        match self {
+            RateLimitKey::ClientIp => client_ip(parts, trusted_proxies).map(|ip| ip.to_string()),
+            RateLimitKey::Header(name) => parts
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .filter(|value| !value.is_empty())
+                .map(ToOwned::to_owned),
+            RateLimitKey::Custom(extract) => extract(parts),
+        }
+    }
+}
+
+/// the client address, only the `x-forwarded-for` appended by the trusted proxies is believed
+fn client_ip(parts: &Parts, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let ConnectInfo(peer) = parts.extensions.get::<ConnectInfo<SocketAddr>>()?;
+    let mut client = peer.ip();
+    // each proxy appends the address it received from, walking back from the rightmost
+    let hops = parts
+        .headers
+        .get_all("x-forwarded-for")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .collect::<Vec<_>>();
+    for hop in hops.into_iter().rev() {
+        if !trusted_proxies.contains(&client) {
+            break;
+        }
+        match hop.trim().parse() {
+            Ok(ip) => client = ip,
+            // the hops before a malformed one are not believable
+            Err(_) => break,
+        }
+    }
+    Some(client)
+}
+
+/// the limit of the routes matching `route`
+///
+/// with `serde` feature, the `period` is seconds like `1.5` or a string like `"500ms"`, `"30s"`, `"2m"`
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct RateLimitRule {
+    /// the route pattern like `/users/:id`, or the prefix ending with `*` like `/api/*`.
+    /// Just `*` for all routes
+    pub route: String,
+    pub limit: u32,
+    #[cfg_attr(feature = "serde", serde(with = "crate::effect_utils::duration"))]
+    pub period: Duration,
+    pub key: RateLimitKey,
+}
+
+impl RateLimitRule {
+    fn matches(&self, route: &str) -> bool {
+        match self.route.strip_suffix('*') {
+            Some(prefix) => route.starts_with(prefix),
+            None => self.route == route,
+        }
+    }
+}
+
+/// the in-memory [RateLimitStore] used without [RateLimitBackend]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum RateLimitAlgorithm {
+    #[default]
+    TokenBucket,
+    SlidingWindow,
+}
+
+/// the config of [SetRateLimit], can be provided by the config `Provider`
+///
+/// with `serde` feature, it can be deserialized, the missing fields are default
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize), serde(default))]
+pub struct RateLimitConfig {
+    /// the first matching rule is applied, the request matching none is not limited
+    pub rules: Vec<RateLimitRule>,
+    pub algorithm: RateLimitAlgorithm,
+    /// the address of the proxies in front of the server, their `x-forwarded-for` is believed
+    /// by [RateLimitKey::ClientIp]. Empty by default, thus the header is ignored
+    pub trusted_proxies: Vec<IpAddr>,
+}
+
+/// the invalid [RateLimitConfig]
+#[derive(Debug, thiserror::Error)]
+pub enum RateLimitConfigError {
+    #[error("the limit of rate limit rule `{0}` is zero")]
+    ZeroLimit(String),
+    #[error("the period of rate limit rule `{0}` is zero")]
+    ZeroPeriod(String),
+}
+
+/// [PrepareMiddlewareEffect] limiting the requests of each client per route,
+/// responding `429 Too Many Requests` with `Retry-After`
+///
+/// the counters are in the [RateLimitBackend] from the state if exist,
+/// otherwise in memory by the [RateLimitAlgorithm]. The store failure does not reject the request
+///
+/// ## Note
+/// the rules match against the [MatchedPath], the route pattern like `/users/:id`.
+/// The router applies the middleware to each route, thus the [MatchedPath] is set in any
+/// [Phase](crate::phase::Phase), [Phase::Outermost](crate::phase::Phase::Outermost) included.
+/// The request matching no route and the service of `preparing_test` have no [MatchedPath],
+/// the rules match against the raw path instead, where only the prefix rules like `/api/*` work
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// use axum_starter::{
+///     prepare,
+///     rate_limit::{RateLimitConfig, RateLimitConfigError, RateLimitKey, RateLimitRule, SetRateLimit},
+/// };
+///
+/// #[prepare(RateLimit?)]
+/// fn rate_limit() -> Result<SetRateLimit, RateLimitConfigError> {
+///     SetRateLimit::new(RateLimitConfig {
+///         rules: vec![RateLimitRule {
+///             route: "/api/*".into(),
+///             limit: 100,
+///             period: Duration::from_secs(60),
+///             key: RateLimitKey::ClientIp,
+///         }],
+///         ..Default::default()
+///     })
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SetRateLimit(RateLimitConfig);
+
+impl SetRateLimit {
+    pub fn new(config: RateLimitConfig) -> Result<Self, RateLimitConfigError> {
+        for rule in &config.rules {
+            if rule.limit == 0 {
+                return Err(RateLimitConfigError::ZeroLimit(rule.route.clone()));
+            }
+            if rule.period.is_zero() {
+                return Err(RateLimitConfigError::ZeroPeriod(rule.route.clone()));
+            }
+        }
+        Ok(Self(config))
+    }
+}
+
+impl<S: 'static> PrepareMiddlewareEffect<S> for SetRateLimit {
+    type Middleware = RateLimitLayer;
+
+    fn take(self, states: &mut StateCollector) -> Self::Middleware {
+        let store = match (states.get::<RateLimitBackend>(), self.0.algorithm) {
+            (Ok(RateLimitBackend(store)), _) => store,
+            (Err(_), RateLimitAlgorithm::TokenBucket) => Arc::new(TokenBucketStore::default()),
+            (Err(_), RateLimitAlgorithm::SlidingWindow) => Arc::new(SlidingWindowStore::default()),
+        };
+        debug!(
+            rate_limit.rules = self.0.rules.len(),
+            rate_limit.algorithm = ?self.0.algorithm,
+            "Set Rate Limit"
+        );
+        RateLimitLayer {
+            rules: self.0.rules.into(),
+            trusted_proxies: self.0.trusted_proxies.into(),
+            store,
+        }
+    }
+}
+
+/// the [Layer] of [SetRateLimit]
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    rules: Arc<[RateLimitRule]>,
+    trusted_proxies: Arc<[IpAddr]>,
+    store: Arc<dyn RateLimitStore>,
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    layer: RateLimitLayer,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RateLimitService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    Response<ResBody>: IntoResponse,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<ReqBody>) -> Self::Future {
+        let (parts, body) = req.into_parts();
+        let route = parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(MatchedPath::as_str)
+            .unwrap_or(parts.uri.path());
+        let limited = self
+            .layer
+            .rules
+            .iter()
+            .enumerate()
+            .find(|(_, rule)| rule.matches(route))
+            .and_then(|(idx, rule)| {
+                let key = rule.key.extract(&parts, &self.layer.trusted_proxies)?;
+                // each rule has its own counters
+                Some((format!("{idx}:{key}"), rule.limit, rule.period))
+            });
+        let req = Request::from_parts(parts, body);
+
+        // the service ready is taken, leave the clone for the next call
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let store = Arc::clone(&self.layer.store);
+        Box::pin(async move {
+            if let Some((key, limit, period)) = limited {
+                match store.acquire(&key, limit, period).await {
+                    Ok(Decision::Allowed { .. }) => {}
+                    Ok(Decision::Limited { retry_after }) => {
+                        debug!(rate_limit.key = key, "Rate Limited");
+                        // round up, thus the client retry after the permit available
+                        let secs =
+                            retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                        return Ok((
+                            StatusCode::TOO_MANY_REQUESTS,
+                            [(RETRY_AFTER, secs.to_string())],
+                        )
+                            .into_response());
+                    }
+                    #[cfg_attr(not(feature = "logger"), allow(unused_variables))]
+                    Err(err) => {
+                        warn!(rate_limit.key = key, error = %err, "Rate Limit Store Failure");
+                    }
+                }
+            }
+            inner.call(req).await.map(IntoResponse::into_response)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        convert::Infallible,
+        net::{IpAddr, SocketAddr},
+        time::Duration,
+    };
+
+    use axum::{body::Body, extract::ConnectInfo, response::Response, routing::get, Router};
+    use http::{header::RETRY_AFTER, Request, StatusCode};
+    use tower::{Layer, Service, ServiceBuilder};
+
+    use super::{
+        Decision, RateLimitConfig, RateLimitConfigError, RateLimitKey, RateLimitRule, SetRateLimit,
+        SlidingWindowStore, TokenBucketStore,
+    };
+    use crate::{
+        phase::{layer_router, MiddlewareOrder, Phase, Phased},
+        PrepareMiddlewareEffect, StateCollector,
+    };
+
+    #[test]
+    fn test_sliding_window() {
+        let store = SlidingWindowStore::default();
+        let period = Duration::from_secs(60);
+        assert_eq!(
+            store.take("a", 2, period),
+            Decision::Allowed { remaining: 1 }
+        );
+        assert_eq!(
+            store.take("a", 2, period),
+            Decision::Allowed { remaining: 0 }
+        );
+        assert!(matches!(
+            store.take("a", 2, period),
+            Decision::Limited { retry_after } if retry_after <= period
+        ));
+        assert_eq!(
+            store.take("b", 2, period),
+            Decision::Allowed { remaining: 1 }
+        );
+    }
+
+    #[test]
+    fn test_zero_limit_or_period() {
+        let store = SlidingWindowStore::default();
+        assert!(matches!(
+            store.take("a", 0, Duration::from_secs(1)),
+            Decision::Limited { .. }
+        ));
+        assert_eq!(
+            store.take("a", 5, Duration::ZERO),
+            Decision::Allowed { remaining: 5 }
+        );
+        assert!(matches!(
+            TokenBucketStore::default().take("a", 0, Duration::from_secs(1)),
+            Decision::Limited { .. }
+        ));
+
+        let rule = |limit, period| RateLimitConfig {
+            rules: vec![RateLimitRule {
+                route: "*".into(),
+                limit,
+                period,
+                key: RateLimitKey::ClientIp,
+            }],
+            ..Default::default()
+        };
+        assert!(matches!(
+            SetRateLimit::new(rule(0, Duration::from_secs(1))),
+            Err(RateLimitConfigError::ZeroLimit(_))
+        ));
+        assert!(matches!(
+            SetRateLimit::new(rule(1, Duration::ZERO)),
+            Err(RateLimitConfigError::ZeroPeriod(_))
+        ));
+    }
+
+    fn request(uri: &str, peer: [u8; 4], forwarded: &str) -> Request<Body> {
+        let mut req = Request::builder()
+            .uri(uri)
+            .header("x-forwarded-for", forwarded)
+            .body(Body::empty())
+            .unwrap();
+        req.extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from((peer, 4000))));
+        req
+    }
+
+    fn limited_router(
+        trusted_proxies: Vec<IpAddr>,
+    ) -> impl Service<Request<Body>, Response = Response, Error = Infallible> {
+        let layer = PrepareMiddlewareEffect::<()>::take(
+            SetRateLimit::new(RateLimitConfig {
+                rules: vec![RateLimitRule {
+                    route: "/api/*".into(),
+                    limit: 1,
+                    period: Duration::from_secs(60),
+                    key: RateLimitKey::ClientIp,
+                }],
+                trusted_proxies,
+                ..Default::default()
+            })
+            .unwrap(),
+            &mut StateCollector::new(),
+        );
+        layer.layer(
+            Router::<()>::new()
+                .route("/api/users", get(|| async { "users" }))
+                .route("/health", get(|| async { "ok" })),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit() {
+        let mut router = limited_router(vec![]);
+        let mut call = |uri, peer| router.call(request(uri, peer, "9.9.9.9"));
+
+        assert_eq!(
+            call("/api/users", [1, 1, 1, 1]).await.unwrap().status(),
+            StatusCode::OK
+        );
+        let resp = call("/api/users", [1, 1, 1, 1]).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers()[RETRY_AFTER], "60");
+        assert_eq!(
+            call("/api/users", [2, 2, 2, 2]).await.unwrap().status(),
+            StatusCode::OK
+        );
+        assert_eq!(
+            call("/health", [1, 1, 1, 1]).await.unwrap().status(),
+            StatusCode::OK
+        );
+    }
+
+    #[tokio::test]
+    async fn test_outermost_matched_path() {
+        let mut states = StateCollector::new();
+        PrepareMiddlewareEffect::<()>::take(
+            Phased::new(
+                Phase::Outermost,
+                SetRateLimit::new(RateLimitConfig {
+                    rules: vec![RateLimitRule {
+                        route: "/users/:id".into(),
+                        limit: 1,
+                        period: Duration::from_secs(60),
+                        key: RateLimitKey::ClientIp,
+                    }],
+                    ..Default::default()
+                })
+                .unwrap(),
+            ),
+            &mut states,
+        );
+        let mut router = layer_router(
+            Router::<()>::new().route("/users/:id", get(|| async { "user" })),
+            ServiceBuilder::new(),
+            MiddlewareOrder::take(&mut states).into_layers(),
+        );
+
+        let status = router
+            .call(request("/users/1", [1, 1, 1, 1], ""))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::OK);
+        // the same route pattern shares the counter
+        let status = router
+            .call(request("/users/2", [1, 1, 1, 1], ""))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[cfg(all(feature = "serde", feature = "serde_json"))]
+    #[test]
+    fn test_deserialize() {
+        use serde_json::{from_value, json};
+
+        use super::RateLimitAlgorithm;
+
+        let config = from_value::<RateLimitConfig>(json!({
+            "rules": [
+                { "route": "/api/*", "limit": 100, "period": "1m" },
+                { "route": "/login", "limit": 5, "period": 30, "key": { "header": "x-api-key" } },
+            ],
+            "algorithm": "sliding_window",
+            "trusted_proxies": ["10.0.0.1"],
+        }))
+        .unwrap();
+        assert_eq!(config.algorithm, RateLimitAlgorithm::SlidingWindow);
+        assert_eq!(config.trusted_proxies, [IpAddr::from([10, 0, 0, 1])]);
+        assert_eq!(config.rules[0].period, Duration::from_secs(60));
+        assert!(matches!(config.rules[0].key, RateLimitKey::ClientIp));
+        assert!(matches!(
+            &config.rules[1].key,
+            RateLimitKey::Header(name) if name == "x-api-key"
+        ));
+
+        let key = from_value::<RateLimitKey>(json!("client_ip")).unwrap();
+        assert!(matches!(key, RateLimitKey::ClientIp));
+        assert!(from_value::<RateLimitKey>(json!("custom")).is_err());
+        assert!(from_value::<RateLimitKey>(json!({ "header": "bad header" })).is_err());
+        assert_eq!(
+            from_value::<RateLimitConfig>(json!({})).unwrap().algorithm,
+            RateLimitAlgorithm::TokenBucket
+        );
+    }
+
+    #[tokio::test]
+    async fn test_spoofed_forwarded_for() {
+        // the header from an untrusted peer is ignored
+        let mut router = limited_router(vec![]);
+        let status = router
+            .call(request("/api/users", [1, 1, 1, 1], "3.3.3.3"))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::OK);
+        let status = router
+            .call(request("/api/users", [1, 1, 1, 1], "4.4.4.4"))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+        // behind a trusted proxy, the client can only spoof the hops left of its own address
+        let mut router = limited_router(vec![IpAddr::from([10, 0, 0, 1])]);
+        let status = router
+            .call(request("/api/users", [10, 0, 0, 1], "3.3.3.3, 5.5.5.5"))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::OK);
+        let status = router
+            .call(request("/api/users", [10, 0, 0, 1], "4.4.4.4, 5.5.5.5"))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+        let status = router
+            .call(request("/api/users", [10, 0, 0, 1], "6.6.6.6"))
+            .await
+            .unwrap()
+            .status();
+        assert_eq!(status, StatusCode::OK);
+    }
+}
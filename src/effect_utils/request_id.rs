@@ -0,0 +1,279 @@
+use std::{
+    collections::hash_map::RandomState,
+    fmt::{Debug, Formatter},
+    hash::{BuildHasher, Hasher},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use http::{HeaderName, HeaderValue, Request, Response};
+use tower::{Layer, Service};
+
+use crate::prepare_behave::{effect_traits::PrepareMiddlewareEffect, StateCollector};
+
+tokio::task_local! {
+    static CURRENT: RequestId;
+}
+
+/// the id of the handling request, in the request extensions
+///
+/// while handling the request, it is also available by [RequestId::current],
+/// thus the outgoing calls can carry it without passing around
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RequestId(HeaderValue);
+
+impl RequestId {
+    /// the id of the request handled by current task
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    pub fn as_str(&self) -> &str {
+        // generated or accepted ids are always visible ASCII
+        self.0.to_str().unwrap_or_default()
+    }
+
+    pub fn header_value(&self) -> &HeaderValue {
+        &self.0
+    }
+}
+
+/// how to generate the [RequestId] when the request has not one
+#[derive(Clone)]
+pub struct IdGenerator(Arc<dyn Fn() -> String + Send + Sync>);
+
+impl Debug for IdGenerator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("IdGenerator")
+    }
+}
+
+impl Default for IdGenerator {
+    fn default() -> Self {
+        Self::random()
+    }
+}
+
+impl IdGenerator {
+    pub fn new<F>(generator: F) -> Self
+    where
+        F: Fn() -> String + Send + Sync + 'static,
+    {
+        Self(Arc::new(generator))
+    }
+
+    /// 128 bits random id in hex
+    pub fn random() -> Self {
+        static SEQ: AtomicU64 = AtomicU64::new(0);
+        Self::new(|| {
+            let seq = SEQ.fetch_add(1, Ordering::Relaxed);
+            let hash = |salt: u64| {
+                let mut hasher = RandomState::new().build_hasher();
+                hasher.write_u64(seq);
+                hasher.write_u64(salt);
+                hasher.finish()
+            };
+            format!("{:016x}{:016x}", hash(0), hash(1))
+        })
+    }
+
+    /// increasing number start from 1 in this process
+    pub fn sequential() -> Self {
+        let seq = Arc::new(AtomicU64::new(0));
+        Self::new(move || (seq.fetch_add(1, Ordering::Relaxed) + 1).to_string())
+    }
+
+    fn generate(&self) -> Option<HeaderValue> {
+        HeaderValue::try_from((self.0)()).ok()
+    }
+}
+
+/// the config of [SetRequestId], can be provided by the config `Provider`
+///
+/// ```rust
+/// use axum_starter::{prepare, request_id::{RequestIdConfig, SetRequestId}};
+///
+/// #[prepare(RequestIdPrepare)]
+/// fn request_id(config: RequestIdConfig) -> SetRequestId {
+///     SetRequestId::new(config)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RequestIdConfig {
+    /// the header carrying the id, default is `x-request-id`
+    pub header: HeaderName,
+    /// generate the id if the request has not one
+    pub generator: IdGenerator,
+    /// accept the id from the request header, default is `true`.
+    /// Otherwise, always generate a new one
+    pub accept_incoming: bool,
+    /// the max length of accepted id in bytes, default is `128`.
+    /// The longer one is replaced by a generated id, thus not logged or echoed
+    pub max_incoming_len: usize,
+}
+
+impl Default for RequestIdConfig {
+    fn default() -> Self {
+        Self {
+            header: HeaderName::from_static("x-request-id"),
+            generator: IdGenerator::random(),
+            accept_incoming: true,
+            max_incoming_len: 128,
+        }
+    }
+}
+
+/// [PrepareMiddlewareEffect] giving every request a [RequestId]
+///
+/// - the id is accepted from the request header or generated, the too long one is not accepted
+/// - then inserted into the request extensions and the request header
+/// - the request is handled inside a `request` span with field `request.id` (with `logger` feature)
+/// - the id is echoed in the response header
+#[derive(Debug, Clone, Default)]
+pub struct SetRequestId(RequestIdConfig);
+
+impl SetRequestId {
+    pub fn new(config: RequestIdConfig) -> Self {
+        Self(config)
+    }
+}
+
+impl<S> PrepareMiddlewareEffect<S> for SetRequestId
+where
+    S: 'static,
+{
+    type Middleware = RequestIdLayer;
+
+    fn take(self, _: &mut StateCollector) -> Self::Middleware {
+        debug!(request_id.header = %self.0.header, "Set Request Id");
+        RequestIdLayer(Arc::new(self.0))
+    }
+}
+
+/// the [Layer] of [SetRequestId]
+#[derive(Debug, Clone)]
+pub struct RequestIdLayer(Arc<RequestIdConfig>);
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RequestIdService {
+            inner,
+            config: Arc::clone(&self.0),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RequestIdService<S> {
+    inner: S,
+    config: Arc<RequestIdConfig>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for RequestIdService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<S::Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let header = &self.config.header;
+        let id = req
+            .headers()
+            .get(header)
+            .filter(|_| self.config.accept_incoming)
+            .filter(|value| value.len() <= self.config.max_incoming_len)
+            .filter(|value| value.to_str().is_ok_and(|id| !id.is_empty()))
+            .cloned()
+            .or_else(|| self.config.generator.generate());
+
+        let Some(id) = id.map(RequestId) else {
+            // the generated id is not a valid header value
+            warn!(request_id.header = %header, "Generate Request Id Failure");
+            return self.inner.call(req).boxed();
+        };
+        req.headers_mut()
+            .insert(header.clone(), id.header_value().clone());
+        req.extensions_mut().insert(id.clone());
+
+        let header = header.clone();
+        let fut = self.inner.call(req);
+        #[cfg(feature = "logger")]
+        let fut = tracing::Instrument::instrument(
+            fut,
+            tracing::info_span!("request", request.id = id.as_str()),
+        );
+        CURRENT
+            .scope(id.clone(), fut)
+            .map(move |ret| {
+                ret.map(|mut resp| {
+                    resp.headers_mut()
+                        .entry(header)
+                        .or_insert_with(|| id.header_value().clone());
+                    resp
+                })
+            })
+            .boxed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{body::Body, routing::get, Extension, Router};
+    use http::Request;
+    use tower::{Layer, Service};
+
+    use super::{IdGenerator, RequestId, RequestIdConfig, RequestIdLayer};
+
+    #[tokio::test]
+    async fn test_request_id() {
+        let config = RequestIdConfig {
+            generator: IdGenerator::sequential(),
+            ..Default::default()
+        };
+        let mut router = RequestIdLayer(config.into()).layer(Router::<()>::new().route(
+            "/",
+            get(|Extension(id): Extension<RequestId>| async move {
+                assert_eq!(RequestId::current(), Some(id.clone()));
+                id.as_str().to_owned()
+            }),
+        ));
+
+        let resp = router.call(Request::new(Body::empty())).await.unwrap();
+        assert_eq!(resp.headers()["x-request-id"], "1");
+
+        let req = Request::builder()
+            .header("x-request-id", "abc")
+            .body(Body::empty())
+            .unwrap();
+        let resp = router.call(req).await.unwrap();
+        assert_eq!(resp.headers()["x-request-id"], "abc");
+
+        // too long, replaced by a generated one
+        let req = Request::builder()
+            .header("x-request-id", "a".repeat(129))
+            .body(Body::empty())
+            .unwrap();
+        let resp = router.call(req).await.unwrap();
+        assert_eq!(resp.headers()["x-request-id"], "2");
+
+        let req = Request::builder()
+            .header("x-request-id", "a".repeat(128))
+            .body(Body::empty())
+            .unwrap();
+        let resp = router.call(req).await.unwrap();
+        assert_eq!(resp.headers()["x-request-id"], "a".repeat(128).as_str());
+    }
+}
@@ -1,9 +1,23 @@
-use std::marker::PhantomData;
+use std::{convert::Infallible, marker::PhantomData};
 
-use axum::{handler::Handler, routing::MethodRouter, Router};
+use axum::{
+    extract::Request,
+    handler::Handler,
+    response::IntoResponse,
+    routing::{MethodRouter, Route as AxumRoute},
+    Router,
+};
+use tower::{Layer, Service};
 
 use crate::prepare_behave::effect_traits::PrepareRouteEffect;
 
+#[cfg(feature = "debug-state")]
+pub use super::debug_state::DebugState;
+#[cfg(feature = "health")]
+pub use super::health::HealthRoutes;
+#[cfg(feature = "metrics")]
+pub use super::metrics::MetricsRoute;
+
 /// [PrepareRouteEffect] add route
 ///
 /// ## Note
@@ -16,6 +30,40 @@ impl<S> Route<S> {
     }
 }
 
+impl<S> Route<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    /// apply the middleware only on this route
+    ///
+    /// ## Note
+    /// calling [MethodRouter::layer](MethodRouter::layer)
+    pub fn layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<AxumRoute> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        Self(self.0, self.1.layer(layer))
+    }
+
+    /// apply the middleware only on this route, and only if the method matched
+    ///
+    /// ## Note
+    /// calling [MethodRouter::route_layer](MethodRouter::route_layer)
+    pub fn route_layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<AxumRoute> + Clone + Send + 'static,
+        L::Service: Service<Request, Error = Infallible> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        Self(self.0, self.1.route_layer(layer))
+    }
+}
+
 impl<S: 'static> PrepareRouteEffect<S> for Route<S> {
     fn set_route(self, route: Router<S>) -> Router<S>
     where
@@ -65,6 +113,47 @@ impl<S> Nest<S> {
     }
 }
 
+impl<S> Nest<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    /// apply the middleware only on the nested router, including its fallback
+    ///
+    /// ## Note
+    /// calling [Router::layer](Router::layer)
+    pub fn layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<AxumRoute> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        Self {
+            path: self.path,
+            router: self.router.layer(layer),
+        }
+    }
+
+    /// apply the middleware only on the matched routes of the nested router
+    ///
+    /// ## Note
+    /// calling [Router::route_layer](Router::route_layer)
+    pub fn route_layer<L>(self, layer: L) -> Self
+    where
+        L: Layer<AxumRoute> + Clone + Send + 'static,
+        L::Service: Service<Request> + Clone + Send + 'static,
+        <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+        <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        Self {
+            path: self.path,
+            router: self.router.route_layer(layer),
+        }
+    }
+}
+
 impl<S> PrepareRouteEffect<S> for Nest<S>
 where
     S: Clone + Send + Sync + 'static,
@@ -74,6 +163,60 @@ where
     }
 }
 
+/// [PrepareRouteEffect] applying the middleware only on the routes of another [PrepareRouteEffect],
+/// for instance the auth for the admin routes
+///
+/// the routes of `R` are added into a new [Router] with the middleware, then merged into the server router,
+/// thus the other routes and the fallback are not affected
+///
+/// ```rust
+/// use axum::{middleware::from_fn, routing::get};
+/// use axum_starter::{prepare, router::{Route, Scoped}, PrepareRouteEffect};
+///
+/// # async fn auth(req: axum::extract::Request, next: axum::middleware::Next) -> axum::response::Response {
+/// #     next.run(req).await
+/// # }
+/// #[prepare(AdminRoutes)]
+/// fn admin_routes<S>() -> impl PrepareRouteEffect<S>
+/// where
+///     S: Clone + Send + Sync + 'static,
+/// {
+///     Scoped::new(
+///         (
+///             Route::new("/admin/users", get(|| async { "users" })),
+///             Route::new("/admin/stats", get(|| async { "stats" })),
+///         ),
+///         from_fn(auth),
+///     )
+/// }
+/// ```
+pub struct Scoped<R, L> {
+    effect: R,
+    layer: L,
+}
+
+impl<R, L> Scoped<R, L> {
+    pub fn new(effect: R, layer: L) -> Self {
+        Self { effect, layer }
+    }
+}
+
+impl<S, R, L> PrepareRouteEffect<S> for Scoped<R, L>
+where
+    S: Clone + Send + Sync + 'static,
+    R: PrepareRouteEffect<S>,
+    L: Layer<AxumRoute> + Clone + Send + 'static,
+    L::Service: Service<Request> + Clone + Send + 'static,
+    <L::Service as Service<Request>>::Response: IntoResponse + 'static,
+    <L::Service as Service<Request>>::Error: Into<Infallible> + 'static,
+    <L::Service as Service<Request>>::Future: Send + 'static,
+{
+    fn set_route(self, route: Router<S>) -> Router<S> {
+        let scoped = self.effect.set_route(Router::new()).route_layer(self.layer);
+        route.merge(scoped)
+    }
+}
+
 /// [PrepareRouteEffect] set fallback handle
 ///
 /// ## Note
@@ -101,3 +244,45 @@ where
         route.fallback(self.handle)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use axum::{
+        body::Body,
+        extract::Request,
+        middleware::{from_fn, Next},
+        response::{IntoResponse, Response},
+        routing::get,
+        Router,
+    };
+    use http::StatusCode;
+    use tower::Service;
+
+    use super::{Route, Scoped};
+    use crate::PrepareRouteEffect;
+
+    async fn deny(_: Request, _: Next) -> Response {
+        StatusCode::UNAUTHORIZED.into_response()
+    }
+
+    #[tokio::test]
+    async fn test_scoped_layer() {
+        let mut router = (
+            Route::new("/public", get(|| async { "public" })),
+            Scoped::new(
+                Route::new("/admin", get(|| async { "admin" })),
+                from_fn(deny),
+            ),
+        )
+            .set_route(Router::<()>::new());
+
+        let mut status = |uri| {
+            let req = Request::builder().uri(uri).body(Body::empty()).unwrap();
+            let resp = router.call(req);
+            async move { resp.await.unwrap().status() }
+        };
+        assert_eq!(status("/public").await, StatusCode::OK);
+        assert_eq!(status("/admin").await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status("/missing").await, StatusCode::NOT_FOUND);
+    }
+}
@@ -1,5 +1,7 @@
 use tower::layer::util::Identity;
 
+pub use super::lazy::{AddLazyState, Lazy, LazyInitError, LazyState};
+
 use crate::prepare_behave::{
     effect_traits::{PrepareMiddlewareEffect, PrepareStateEffect},
     StateCollector,
@@ -31,3 +33,39 @@ impl<S> AddState<S> {
         Self(state)
     }
 }
+
+/// [PrepareStateEffect] or [PrepareMiddlewareEffect] adding a new state type with a key
+///
+/// thus multiply values of the same type can be added, fetch it by
+/// [`StateCollector::take_keyed`] or `#[state(key = "...")]`
+pub struct AddKeyedState<S> {
+    key: String,
+    state: S,
+}
+
+impl<S> AddKeyedState<S> {
+    pub fn new(key: impl Into<String>, state: S) -> Self
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        Self {
+            key: key.into(),
+            state,
+        }
+    }
+}
+
+impl<State: 'static, Service> PrepareMiddlewareEffect<Service> for AddKeyedState<State> {
+    type Middleware = Identity;
+
+    fn take(self, states: &mut StateCollector) -> Self::Middleware {
+        self.take_state(states);
+        Identity::new()
+    }
+}
+
+impl<S: 'static> PrepareStateEffect for AddKeyedState<S> {
+    fn take_state(self, states: &mut StateCollector) {
+        states.insert_keyed(self.key, self.state)
+    }
+}
@@ -0,0 +1,451 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    error,
+    future::Future,
+    io,
+    sync::Arc,
+    time::Duration,
+};
+
+use futures::{
+    future::{join_all, select, BoxFuture, Either},
+    FutureExt,
+};
+use tokio::{
+    sync::{mpsc, watch},
+    task::{JoinError, JoinHandle},
+};
+
+use crate::prepare_behave::{
+    effect_collectors::state_collector::Aggregate, effect_traits::PrepareStateEffect,
+    StateCollector,
+};
+
+type BoxError = Box<dyn error::Error + Send + Sync>;
+type WorkerTask<S> =
+    Arc<dyn Fn(S, Shutdown) -> BoxFuture<'static, Result<(), BoxError>> + Send + Sync>;
+
+/// the shutdown signal for [Worker], fired when the graceful shutdown signal arrive
+/// or the server stopped
+#[derive(Debug, Clone)]
+pub struct Shutdown(watch::Receiver<bool>);
+
+impl Shutdown {
+    pub(crate) fn new(receiver: watch::Receiver<bool>) -> Self {
+        Self(receiver)
+    }
+
+    /// whether the shutdown signal has been fired
+    pub fn is_shutdown(&self) -> bool {
+        *self.0.borrow() || self.0.has_changed().is_err()
+    }
+
+    /// wait until the shutdown signal fired
+    pub async fn wait(&mut self) {
+        while !*self.0.borrow_and_update() {
+            if self.0.changed().await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+/// the delay between restarts of a [Worker], doubled after each restart
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    max_restarts: Option<usize>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+impl Backoff {
+    pub fn new(initial: Duration, max: Duration) -> Self {
+        Self {
+            initial,
+            max,
+            max_restarts: None,
+        }
+    }
+
+    /// give up after restarting `max` times
+    pub fn max_restarts(mut self, max: usize) -> Self {
+        self.max_restarts = Some(max);
+        self
+    }
+
+    fn delay(&self, restarts: usize) -> Duration {
+        let factor = 1u32
+            .checked_shl(restarts.min(31) as u32)
+            .unwrap_or(u32::MAX);
+        self.initial.saturating_mul(factor).min(self.max)
+    }
+}
+
+/// whether restart the [Worker] after it stopped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RestartPolicy {
+    /// never restart
+    #[default]
+    Never,
+    /// restart when the worker return error or panic
+    OnFailure(Backoff),
+    /// restart whenever the worker stopped
+    Always(Backoff),
+}
+
+impl RestartPolicy {
+    /// the backoff if restart is required
+    fn backoff(&self, failed: bool, restarts: usize) -> Option<Duration> {
+        let backoff = match self {
+            RestartPolicy::OnFailure(backoff) if failed => backoff,
+            RestartPolicy::Always(backoff) => backoff,
+            _ => return None,
+        };
+        match backoff.max_restarts {
+            Some(max) if restarts >= max => None,
+            _ => Some(backoff.delay(restarts)),
+        }
+    }
+}
+
+/// [PrepareStateEffect] registering a long-running background task, supervised after the server
+/// start, require the State type `S`
+///
+/// - the task receive a clone of the State and the [Shutdown] signal, it should return
+///   after the signal fired
+/// - the task is restarted by the [RestartPolicy], the panic is treated as failure
+/// - the [Worker::fatal] worker's final failure will stop the server with [WorkerFailure]
+///
+/// ## Note
+/// - the worker is not run by `preparing_test`
+/// - the `S` should be the State of the server, otherwise preparing fail with [UnclaimedWorker]
+pub struct Worker<S> {
+    name: String,
+    task: WorkerTask<S>,
+    restart: RestartPolicy,
+    fatal: bool,
+}
+
+impl<S: Send + 'static> Worker<S> {
+    pub fn new<F, Fut, E>(name: impl Into<String>, task: F) -> Self
+    where
+        F: Fn(S, Shutdown) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Into<BoxError>,
+    {
+        Self {
+            name: name.into(),
+            task: Arc::new(move |state, shutdown| {
+                task(state, shutdown)
+                    .map(|ret| ret.map_err(Into::into))
+                    .boxed()
+            }),
+            restart: RestartPolicy::default(),
+            fatal: false,
+        }
+    }
+
+    /// set the [RestartPolicy], default is [RestartPolicy::Never]
+    pub fn restart(mut self, policy: RestartPolicy) -> Self {
+        self.restart = policy;
+        self
+    }
+
+    /// the server will stop if the worker failed and not restart any more
+    pub fn fatal(mut self) -> Self {
+        self.fatal = true;
+        self
+    }
+}
+
+/// all [Worker] registered, stored in the [StateCollector]
+pub(crate) struct Workers<S>(pub(crate) Vec<Worker<S>>);
+
+impl<S> Default for Workers<S> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+impl<S: 'static> Aggregate for Workers<S> {
+    fn merge(&mut self, other: Self) {
+        self.0.extend(other.0)
+    }
+}
+
+/// the State type each [Worker] require, for finding the unclaimed [Workers]
+#[derive(Default)]
+struct WorkerStates(Vec<(String, TypeId, &'static str)>);
+
+impl Aggregate for WorkerStates {
+    fn merge(&mut self, other: Self) {
+        self.0.extend(other.0)
+    }
+}
+
+impl<S: 'static> PrepareStateEffect for Worker<S> {
+    fn take_state(self, states: &mut StateCollector) {
+        states.aggregate::<WorkerStates>().0.push((
+            self.name.clone(),
+            TypeId::of::<S>(),
+            type_name::<S>(),
+        ));
+        states.aggregate::<Workers<S>>().0.push(self)
+    }
+}
+
+/// take the [Workers] of the State `S`, the [Worker] require other State type will never run
+pub(crate) fn take_workers<S: 'static>(
+    states: &mut StateCollector,
+) -> Result<Option<Workers<S>>, UnclaimedWorker> {
+    if let Ok(WorkerStates(registered)) = states.take::<WorkerStates>() {
+        if let Some((name, _, require)) = registered
+            .into_iter()
+            .find(|(_, id, _)| *id != TypeId::of::<S>())
+        {
+            return Err(UnclaimedWorker {
+                name,
+                require,
+                state: type_name::<S>(),
+            });
+        }
+    }
+    Ok(states.take::<Workers<S>>().ok())
+}
+
+#[derive(Debug, thiserror::Error)]
+/// the [Worker] require a State type other than the State of the server
+#[error("Worker `{name}` require State `{require}`, but the State is `{state}`")]
+pub struct UnclaimedWorker {
+    pub name: String,
+    pub require: &'static str,
+    pub state: &'static str,
+}
+
+#[derive(Debug, thiserror::Error)]
+/// the fatal [Worker] failed and will not restart
+#[error("Worker `{name}` failure: {reason}")]
+pub struct WorkerFailure {
+    pub name: String,
+    pub reason: String,
+}
+
+fn panic_reason(err: JoinError) -> String {
+    match err.try_into_panic() {
+        Ok(payload) => panic_message(&*payload),
+        Err(err) => err.to_string(),
+    }
+}
+
+/// the message of the panic payload
+pub(crate) fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|msg| msg.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "panic".to_owned())
+}
+
+async fn supervise<S>(
+    worker: Worker<S>,
+    state: S,
+    mut shutdown: Shutdown,
+    fatal: mpsc::UnboundedSender<WorkerFailure>,
+) where
+    S: Clone + Send + 'static,
+{
+    let mut restarts = 0;
+    loop {
+        debug!(worker = worker.name, restarts, "Worker Start");
+        let failure = match tokio::spawn((worker.task)(state.clone(), shutdown.clone())).await {
+            Ok(Ok(())) => None,
+            Ok(Err(err)) => Some(err.to_string()),
+            Err(err) => Some(panic_reason(err)),
+        };
+        #[cfg(feature = "metrics")]
+        if failure.is_some() {
+            crate::metrics::record_worker_failure(&worker.name);
+        }
+        if shutdown.is_shutdown() {
+            break;
+        }
+
+        let Some(delay) = worker.restart.backoff(failure.is_some(), restarts) else {
+            if let Some(reason) = failure {
+                warn!(worker = worker.name, reason, "Worker Failure");
+                if worker.fatal {
+                    let _ = fatal.send(WorkerFailure {
+                        name: worker.name.clone(),
+                        reason,
+                    });
+                }
+            }
+            break;
+        };
+        warn!(worker = worker.name, reason = ?failure, ?delay, "Worker Restarting");
+        restarts += 1;
+        if let Either::Right(_) =
+            select(tokio::time::sleep(delay).boxed(), shutdown.wait().boxed()).await
+        {
+            break;
+        }
+    }
+    debug!(worker = worker.name, "Worker Stopped");
+}
+
+/// the running [Worker]s
+pub(crate) struct WorkerSupervisor {
+    shutdown: Arc<watch::Sender<bool>>,
+    handles: Vec<JoinHandle<()>>,
+    fatal: mpsc::UnboundedReceiver<WorkerFailure>,
+}
+
+impl WorkerSupervisor {
+    pub(crate) fn spawn<S>(workers: Option<Workers<S>>, state: &S) -> Self
+    where
+        S: Clone + Send + 'static,
+    {
+        let (shutdown, receiver) = watch::channel(false);
+        let (fatal_sender, fatal) = mpsc::unbounded_channel();
+        let handles = workers
+            .map(|workers| workers.0)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|worker| {
+                tokio::spawn(supervise(
+                    worker,
+                    state.clone(),
+                    Shutdown::new(receiver.clone()),
+                    fatal_sender.clone(),
+                ))
+            })
+            .collect();
+        Self {
+            shutdown: Arc::new(shutdown),
+            handles,
+            fatal,
+        }
+    }
+
+    /// spawn a task stopping on the shutdown signal, the server will wait for it
+    pub(crate) fn spawn_task<F>(&mut self, task: impl FnOnce(Shutdown) -> F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let shutdown = Shutdown::new(self.shutdown.subscribe());
+        self.handles.push(tokio::spawn(task(shutdown)));
+    }
+
+    /// fire the shutdown signal of workers when `signal` completed
+    pub(crate) fn shutdown_on<F>(&self, signal: F) -> impl Future<Output = ()>
+    where
+        F: Future<Output = ()>,
+    {
+        let shutdown = Arc::clone(&self.shutdown);
+        signal.map(move |_| {
+            shutdown.send_replace(true);
+        })
+    }
+
+    /// run the server until it stopped or a fatal worker failed, then wait for workers stopping
+    pub(crate) async fn run<F>(mut self, server: F) -> io::Result<()>
+    where
+        F: Future<Output = io::Result<()>>,
+    {
+        let fatal = async {
+            match self.fatal.recv().await {
+                Some(failure) => failure,
+                None => futures::future::pending().await,
+            }
+        };
+        let ret = match select(server.boxed_local(), fatal.boxed_local()).await {
+            Either::Left((ret, _)) => ret,
+            Either::Right((failure, _)) => Err(io::Error::other(failure)),
+        };
+        self.shutdown.send_replace(true);
+        join_all(self.handles).await;
+        ret
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use futures::future::pending;
+
+    use super::{take_workers, Backoff, RestartPolicy, Worker, WorkerSupervisor, Workers};
+    use crate::{PrepareStateEffect, StateCollector};
+
+    #[tokio::test]
+    async fn test_fatal_worker() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let worker = Worker::new("failing", |runs: Arc<AtomicUsize>, _| async move {
+            runs.fetch_add(1, Ordering::SeqCst);
+            Err(io::Error::other("boom"))
+        })
+        .restart(RestartPolicy::OnFailure(
+            Backoff::new(Duration::from_millis(1), Duration::from_millis(2)).max_restarts(2),
+        ))
+        .fatal();
+
+        let supervisor = WorkerSupervisor::spawn(Some(Workers(vec![worker])), &runs);
+        let err = supervisor.run(pending()).await.unwrap_err();
+        assert_eq!(err.to_string(), "Worker `failing` failure: boom");
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_worker() {
+        let worker = Worker::new("waiting", |_: (), mut shutdown| async move {
+            shutdown.wait().await;
+            Ok::<_, io::Error>(())
+        })
+        .restart(RestartPolicy::Always(Backoff::default()));
+
+        let supervisor = WorkerSupervisor::spawn(Some(Workers(vec![worker])), &());
+        supervisor.run(async { Ok(()) }).await.unwrap();
+    }
+
+    #[test]
+    fn test_unclaimed_worker() {
+        let mut states = StateCollector::new();
+        Worker::new("counting", |_: u8, _| async { Ok::<_, io::Error>(()) })
+            .take_state(&mut states);
+        Worker::new("other", |_: u16, _| async { Ok::<_, io::Error>(()) }).take_state(&mut states);
+
+        let err = take_workers::<u8>(&mut states).err().unwrap();
+        assert_eq!(err.name, "other");
+        assert_eq!(err.state, "u8");
+
+        let mut states = StateCollector::new();
+        Worker::new("counting", |_: u8, _| async { Ok::<_, io::Error>(()) })
+            .take_state(&mut states);
+        let workers = take_workers::<u8>(&mut states).unwrap().unwrap();
+        assert_eq!(workers.0.len(), 1);
+        assert!(take_workers::<u8>(&mut StateCollector::new())
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_backoff() {
+        let backoff = Backoff::new(Duration::from_secs(1), Duration::from_secs(5));
+        assert_eq!(backoff.delay(0), Duration::from_secs(1));
+        assert_eq!(backoff.delay(2), Duration::from_secs(4));
+        assert_eq!(backoff.delay(40), Duration::from_secs(5));
+    }
+}
@@ -2,6 +2,8 @@
 #![doc = include_str!("../Readme.md")]
 #[macro_use]
 mod log_macro;
+#[macro_use]
+mod tuple_macro;
 
 mod config_provide;
 mod effect_utils;
@@ -11,25 +13,59 @@ mod prepare_sets;
 mod server_prepare;
 mod server_ready;
 
+#[cfg(feature = "cli")]
+mod cli;
+#[cfg(feature = "otlp")]
+mod otlp;
+#[cfg(feature = "subscriber")]
+mod subscriber;
 #[cfg(feature = "test-utils")]
 mod test_utils;
 
+#[doc(hidden)]
+pub use axum::extract::FromRef;
 pub use prepare_behave::effect_collectors::state_collector::{
-    FromStateCollector, StateCollector, TypeNotInState,
+    DuplicatePolicy, DuplicateState, FromStateCollector, StateCheck, StateCollector,
+    TypeNotInState, UncheckedState,
+};
+pub use prepare_behave::effect_collectors::state_inventory::{
+    PrepareRecord, StateInventory, StateRecord,
+};
+pub use prepare_behave::effect_collectors::state_list::{
+    ProduceState, RequireState, StateCons, StateList, StateNil,
 };
 pub use prepare_behave::effect_traits::{
     Prepare, PrepareMiddlewareEffect, PrepareRouteEffect, PrepareStateEffect,
 };
+#[doc(hidden)]
+pub use server_prepare::IntoPrepareResult;
 pub use server_prepare::{
-    BindServe, LoggerInitialization, PrepareDecorator, PrepareError, PrepareStartError,
-    ServeAddress, ServerPrepare,
+    BindServe, BootReport, LoggerInitialization, PrepareArgError, PrepareDecorator, PrepareError,
+    PrepareStartError, PrepareTiming, Schedule, ServeAddress, ServerPrepare, TimingDecorator,
 };
 pub use server_ready::ServerReady;
 
 pub use axum_starter_macro::{prepare, Configure, FromStateCollector, Provider};
-pub use config_provide::provider::Provider;
-pub use effect_utils::{router, state};
+#[cfg(feature = "cli")]
+pub use clap;
+#[cfg(feature = "cli")]
+pub use cli::{Cli, CliCommand, CliConfigure, CliError};
+pub use config_provide::profile::{ConfigProfiles, Profile};
+pub use config_provide::provider::{AnyProvider, Provider};
+pub use config_provide::secret::{Secret, SecretLoadError};
+pub use config_provide::try_provider::{AsyncTryProvider, ProvideError, TryProvider};
+#[cfg(feature = "metrics")]
+pub use effect_utils::metrics;
+#[cfg(feature = "middleware")]
+pub use effect_utils::middleware;
+pub use effect_utils::{health, phase, rate_limit, request_id, router, state, worker};
 pub use futures::future::{ready, Ready};
+#[cfg(feature = "otlp")]
+pub use otlp::{OtlpError, OtlpLogger};
 pub use prepare_sets::{concurrent_set::ConcurrentPrepareSet, serial_set::SerialPrepareSet};
+#[cfg(feature = "subscriber")]
+pub use subscriber::{
+    LogFormat, LogReload, LogReloadHandle, LogSetting, SubscriberError, SubscriberLogger,
+};
 #[cfg(feature = "test-utils")]
 pub use test_utils::TestResponse;
@@ -16,3 +16,12 @@ macro_rules! debug {
         let _ = stringify!($($t)*);
     };
 }
+// #[macro_export(crate)]
+macro_rules! warn {
+    ($($t:tt)*) => {
+        #[cfg(feature = "logger")]
+        ::tracing::warn!($($t)*);
+        #[cfg(not(feature = "logger"))]
+        let _ = stringify!($($t)*);
+    };
+}
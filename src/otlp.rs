@@ -0,0 +1,108 @@
+use std::sync::OnceLock;
+
+use opentelemetry::trace::TracerProvider;
+use opentelemetry_otlp::{ExporterBuildError, SpanExporter, WithExportConfig};
+use opentelemetry_sdk::{trace::SdkTracerProvider, Resource};
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+use crate::LoggerInitialization;
+
+static PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// the logger printing to stdout and exporting the spans by OTLP over HTTP, require `otlp` feature
+///
+/// the spans of each prepare, concurrent set and post prepare task are exported,
+/// with `otel.name` and `otel.status_code` fields. The pending spans are flushed after
+/// [`ServerReady::launch`](crate::ServerReady::launch) finished
+///
+/// ```rust,no_run
+/// use axum_starter::{LoggerInitialization, OtlpError, OtlpLogger};
+///
+/// struct Config;
+///
+/// impl LoggerInitialization for Config {
+///     type Error = OtlpError;
+///
+///     fn init_logger(&self) -> Result<(), Self::Error> {
+///         OtlpLogger::new("my-service").init_logger()
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct OtlpLogger {
+    service_name: String,
+    endpoint: String,
+    level: LevelFilter,
+}
+
+#[derive(Debug, thiserror::Error)]
+/// init [OtlpLogger] failure
+pub enum OtlpError {
+    #[error("build OTLP exporter failure: {0}")]
+    Exporter(#[from] ExporterBuildError),
+    #[error(transparent)]
+    Subscriber(#[from] tracing_subscriber::util::TryInitError),
+}
+
+impl OtlpLogger {
+    /// export to the local collector `http://localhost:4318/v1/traces` at info level
+    pub fn new(service_name: impl Into<String>) -> Self {
+        Self {
+            service_name: service_name.into(),
+            endpoint: String::from("http://localhost:4318/v1/traces"),
+            level: LevelFilter::INFO,
+        }
+    }
+
+    /// the OTLP/HTTP traces endpoint of the collector
+    pub fn endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    /// the max level of both printed events and exported spans
+    pub fn level(mut self, level: LevelFilter) -> Self {
+        self.level = level;
+        self
+    }
+}
+
+impl LoggerInitialization for OtlpLogger {
+    type Error = OtlpError;
+
+    fn init_logger(&self) -> Result<(), Self::Error> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(&self.endpoint)
+            .build()?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(self.service_name.clone())
+                    .build(),
+            )
+            .build();
+        let tracer = provider.tracer(self.service_name.clone());
+
+        tracing_subscriber::registry()
+            .with(self.level)
+            .with(tracing_subscriber::fmt::layer())
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .try_init()?;
+        let _ = PROVIDER.set(provider);
+        Ok(())
+    }
+}
+
+/// flush the pending spans and stop exporting
+pub(crate) async fn shutdown() {
+    let Some(provider) = PROVIDER.get().cloned() else {
+        return;
+    };
+    // the exporter blocks until flushed
+    if let Ok(Err(err)) = tokio::task::spawn_blocking(move || provider.shutdown()).await {
+        warn!(error = %err, "OTLP Shutdown Failure");
+    }
+}
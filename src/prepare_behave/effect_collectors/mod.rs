@@ -1 +1,3 @@
 pub mod state_collector;
+pub mod state_inventory;
+pub mod state_list;
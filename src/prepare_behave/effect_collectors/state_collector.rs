@@ -1,48 +1,402 @@
 use std::{
     any::{type_name, Any, TypeId},
+    cell::Cell,
     collections::HashMap,
     ops::BitAnd,
 };
 
+use crate::prepare_sets::Timing;
+
+use super::state_inventory::{PrepareRecord, StateInventory, StateRecord};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct StateKey {
+    id: TypeId,
+    key: Option<String>,
+}
+
+impl StateKey {
+    fn of<T: 'static>(key: Option<&str>) -> Self {
+        Self {
+            id: TypeId::of::<T>(),
+            key: key.map(ToOwned::to_owned),
+        }
+    }
+}
+
+type MergeFn = fn(&mut Box<dyn Any>, Box<dyn Any>);
+
+/// the state accumulated by several prepares, like the registered health checks
+///
+/// the collectors of concurrent prepares start empty, thus the aggregates in them
+/// are merged rather than overwritten when the collectors combine
+pub(crate) trait Aggregate: Default + 'static {
+    fn merge(&mut self, other: Self);
+}
+
+fn merge_aggregate<T: Aggregate>(value: &mut Box<dyn Any>, other: Box<dyn Any>) {
+    if let (Some(value), Ok(other)) = (value.downcast_mut::<T>(), other.downcast::<T>()) {
+        value.merge(*other)
+    }
+}
+
+struct StateEntry {
+    name: &'static str,
+    value: Box<dyn Any + 'static>,
+    /// whether fetched by [StateCollector::get]
+    fetched: Cell<bool>,
+    /// the type name of the prepare producing it
+    producer: Option<&'static str>,
+    /// [Some] if it is an [Aggregate]
+    merge: Option<MergeFn>,
+}
+
+fn state_name(name: &str, key: Option<&str>) -> String {
+    match key {
+        Some(key) => format!("{name}(key = {key:?})"),
+        None => name.to_owned(),
+    }
+}
+
 /// collect all state during prepare
-pub struct StateCollector(HashMap<TypeId, Box<dyn Any + 'static>>);
+///
+/// the state is identified by its type, or its type with a string key
+/// thus multiply values of the same type can be stored, like primary and replica database pool
+pub struct StateCollector {
+    states: HashMap<StateKey, StateEntry>,
+    overwritten: Vec<String>,
+    /// the prepare currently applying its effect
+    producer: Option<&'static str>,
+    prepares: Vec<PrepareRecord>,
+    /// the stage of the next serial prepare
+    stages: usize,
+}
 
 impl BitAnd for StateCollector {
     type Output = Self;
 
-    fn bitand(self, rhs: Self) -> Self::Output {
-        Self(self.0.into_iter().chain(rhs.0).collect())
+    fn bitand(mut self, rhs: Self) -> Self::Output {
+        self.overwritten.extend(rhs.overwritten);
+        self.prepares.extend(rhs.prepares);
+        for (key, entry) in rhs.states {
+            match self.states.get_mut(&key) {
+                Some(StateEntry {
+                    value,
+                    merge: Some(merge),
+                    ..
+                }) => merge(value, entry.value),
+                _ => self.insert_entry(key, entry),
+            }
+        }
+        self
     }
 }
 
 impl StateCollector {
     pub(crate) fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            states: HashMap::new(),
+            overwritten: Vec::new(),
+            producer: None,
+            prepares: Vec::new(),
+            stages: 0,
+        }
+    }
+
+    /// the states inserted inside `apply` are recorded as produced by the prepare `name`
+    pub(crate) fn produced_by<R>(
+        &mut self,
+        name: &'static str,
+        apply: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        let previous = self.producer.replace(name);
+        let ret = apply(self);
+        self.producer = previous;
+        ret
+    }
+
+    /// the prepare currently applying its effect
+    pub(crate) fn producer(&self) -> Option<&'static str> {
+        self.producer
+    }
+
+    /// the stage of the next serial prepare
+    pub(crate) fn stage(&self) -> usize {
+        self.stages
+    }
+
+    /// record an executed prepare, each serial prepare takes a new stage
+    pub(crate) fn record_prepare(&mut self, name: &'static str, timing: Timing) {
+        self.prepares.push(PrepareRecord {
+            name,
+            stage: self.stages,
+            started: timing.started,
+            duration: timing.duration,
+        });
+        self.stages += 1;
+    }
+
+    /// the prepares recorded in `concurrent` take one new stage
+    pub(crate) fn concurrent_stage(&mut self, concurrent: &mut Self) {
+        if concurrent.prepares.is_empty() {
+            return;
+        }
+        for prepare in &mut concurrent.prepares {
+            prepare.stage = self.stages;
+        }
+        self.stages += 1;
+    }
+
+    fn insert_entry(&mut self, key: StateKey, entry: StateEntry) {
+        let name = entry.name;
+        if self.states.contains_key(&key) {
+            self.overwritten.push(state_name(name, key.key.as_deref()));
+        }
+        self.states.insert(key, entry);
+    }
+
+    fn try_insert_entry(&mut self, key: StateKey, entry: StateEntry) -> Result<(), DuplicateState> {
+        if self.states.contains_key(&key) {
+            return Err(DuplicateState(vec![state_name(
+                entry.name,
+                key.key.as_deref(),
+            )]));
+        }
+        self.states.insert(key, entry);
+        Ok(())
+    }
+
+    fn entry<T: 'static>(&self, data: T) -> StateEntry {
+        StateEntry {
+            name: type_name::<T>(),
+            value: Box::new(data),
+            fetched: Cell::new(false),
+            producer: self.producer,
+            merge: None,
+        }
+    }
+
+    /// the [Aggregate] in the collector, insert the default one if not exist
+    pub(crate) fn aggregate<T: Aggregate>(&mut self) -> &mut T {
+        let producer = self.producer;
+        self.states
+            .entry(StateKey::of::<T>(None))
+            .or_insert_with(|| StateEntry {
+                name: type_name::<T>(),
+                value: Box::new(T::default()),
+                fetched: Cell::new(false),
+                producer,
+                merge: Some(merge_aggregate::<T>),
+            })
+            .value
+            .downcast_mut()
+            .expect("the aggregate is stored by its type")
     }
 
     /// insert a new type into state collect
     ///
-    /// if the type previously exist, the new value will overwrite the old one
+    /// if the type previously exist, the new value will overwrite the old one,
+    /// and the overwritten one will be recorded, see [`DuplicatePolicy`]
     pub fn insert<T: 'static + Any>(&mut self, data: T) {
-        self.0.insert(data.type_id(), Box::new(data));
+        self.insert_entry(StateKey::of::<T>(None), self.entry(data));
+    }
+
+    /// insert a new type into state collect with a key
+    ///
+    /// the values of same type but different keys will not overwrite each other
+    pub fn insert_keyed<T: 'static + Any>(&mut self, key: impl Into<String>, data: T) {
+        let key = StateKey {
+            id: TypeId::of::<T>(),
+            key: Some(key.into()),
+        };
+        self.insert_entry(key, self.entry(data));
+    }
+
+    /// insert a new type into state collect
+    ///
+    /// if the type previously exist, the old one is kept and [DuplicateState] Error is returned
+    pub fn try_insert<T: 'static + Any>(&mut self, data: T) -> Result<(), DuplicateState> {
+        self.try_insert_entry(StateKey::of::<T>(None), self.entry(data))
+    }
+
+    /// keyed version of [StateCollector::try_insert]
+    pub fn try_insert_keyed<T: 'static + Any>(
+        &mut self,
+        key: impl Into<String>,
+        data: T,
+    ) -> Result<(), DuplicateState> {
+        let key = StateKey {
+            id: TypeId::of::<T>(),
+            key: Some(key.into()),
+        };
+        self.try_insert_entry(key, self.entry(data))
+    }
+
+    fn take_by<T: 'static + Any>(&mut self, key: Option<&str>) -> Result<T, TypeNotInState> {
+        self.states
+            .remove(&StateKey::of::<T>(key))
+            .and_then(|entry| entry.value.downcast().ok())
+            .map(|data| *data)
+            .ok_or_else(|| TypeNotInState(state_name(type_name::<T>(), key)))
+    }
+
+    fn get_by<T: 'static + Any + Clone>(&self, key: Option<&str>) -> Result<T, TypeNotInState> {
+        self.states
+            .get(&StateKey::of::<T>(key))
+            .inspect(|entry| entry.fetched.set(true))
+            .and_then(|entry| entry.value.downcast_ref::<T>())
+            .cloned()
+            .ok_or_else(|| TypeNotInState(state_name(type_name::<T>(), key)))
     }
 
     /// take a type from the collector
     ///
     /// if the Value Not exist in collector, it will return [TypeNotInState] Error
     pub fn take<T: 'static + Any>(&mut self) -> Result<T, TypeNotInState> {
-        self.0
-            .remove(&TypeId::of::<T>())
-            .and_then(|data| data.downcast().ok())
-            .map(|data| *data)
-            .ok_or(TypeNotInState(type_name::<T>()))
+        self.take_by(None)
     }
+
+    /// take a type with the key from the collector
+    pub fn take_keyed<T: 'static + Any>(&mut self, key: &str) -> Result<T, TypeNotInState> {
+        self.take_by(Some(key))
+    }
+
+    /// get a clone of a type from the collector, the value keeps in the collector
+    ///
+    /// thus the same value, like a connection pool, can be fetched multiply times.
+    /// if the Value Not exist in collector, it will return [TypeNotInState] Error
+    pub fn get<T: 'static + Any + Clone>(&self) -> Result<T, TypeNotInState> {
+        self.get_by(None)
+    }
+
+    /// get a clone of a type with the key from the collector
+    pub fn get_keyed<T: 'static + Any + Clone>(&self, key: &str) -> Result<T, TypeNotInState> {
+        self.get_by(Some(key))
+    }
+
+    /// the type names of the states which are overwritten by a later insert
+    pub fn overwritten(&self) -> &[String] {
+        &self.overwritten
+    }
+
+    /// the snapshot of the states and the executed prepares
+    pub fn inventory(&self) -> StateInventory {
+        let mut states = self
+            .states
+            .iter()
+            .map(|(key, entry)| StateRecord {
+                name: state_name(entry.name, key.key.as_deref()),
+                producer: entry.producer,
+            })
+            .collect::<Vec<_>>();
+        states.sort_by(|l, r| l.name.cmp(&r.name));
+        StateInventory {
+            states,
+            prepares: self.prepares.clone(),
+        }
+    }
+
+    /// the type names of the states which are never taken or fetched
+    pub fn unconsumed(&self) -> Vec<String> {
+        let mut names = self
+            .states
+            .iter()
+            .filter(|(_, entry)| !entry.fetched.get())
+            .map(|(key, entry)| state_name(entry.name, key.key.as_deref()))
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    }
+}
+
+/// how to handle inserting a state which is already in the [StateCollector]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// the new value overwrite the old one
+    #[default]
+    Overwrite,
+    /// reject starting the server with [DuplicateState] Error
+    Error,
 }
 
 #[derive(Debug, thiserror::Error)]
 #[error("Target Type {0} Not in State")]
 /// then type not in the state collector
-pub struct TypeNotInState(&'static str);
+pub struct TypeNotInState(String);
+
+#[derive(Debug, thiserror::Error)]
+#[error("Duplicate State {}", .0.join(", "))]
+/// the state inserted more than once
+pub struct DuplicateState(Vec<String>);
+
+impl DuplicateState {
+    /// the type names of the duplicate states
+    pub fn names(&self) -> &[String] {
+        &self.0
+    }
+}
+
+/// how to handle the wiring mistakes of state after converting into the State,
+/// see [`ServerPrepare::state_check`](crate::ServerPrepare::state_check)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StateCheck {
+    /// do nothing
+    #[default]
+    Ignore,
+    /// log the unconsumed and overwritten states as warning, require `logger` feature
+    Warn,
+    /// reject starting the server with [UncheckedState] Error
+    Strict,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("State check failure, unconsumed: [{}], overwritten: [{}]", .unconsumed.join(", "), .overwritten.join(", "))]
+/// the state is inserted but never used, or overwritten by a later insert
+pub struct UncheckedState {
+    pub unconsumed: Vec<String>,
+    pub overwritten: Vec<String>,
+}
+
+impl StateCheck {
+    /// check the collector after the State fetched
+    pub(crate) fn check(&self, collector: &StateCollector) -> Result<(), UncheckedState> {
+        if let StateCheck::Ignore = self {
+            return Ok(());
+        }
+        let unconsumed = collector.unconsumed();
+        let overwritten = collector.overwritten().to_vec();
+        if unconsumed.is_empty() && overwritten.is_empty() {
+            return Ok(());
+        }
+
+        match self {
+            StateCheck::Strict => Err(UncheckedState {
+                unconsumed,
+                overwritten,
+            }),
+            _ => {
+                warn!(
+                    state.unconsumed = ?unconsumed,
+                    state.overwritten = ?overwritten,
+                    "State check failure"
+                );
+                Ok(())
+            }
+        }
+    }
+}
+
+impl DuplicatePolicy {
+    /// check the overwritten states of the collector
+    pub(crate) fn check(&self, collector: &StateCollector) -> Result<(), DuplicateState> {
+        match self {
+            DuplicatePolicy::Error if !collector.overwritten.is_empty() => {
+                Err(DuplicateState(collector.overwritten.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+}
 
 /// Mapping type form [StateCollector] to special Type
 pub trait FromStateCollector: Sized {
@@ -72,12 +426,127 @@ macro_rules! state_gen {
     };
 }
 
-state_gen!();
-state_gen!(T1);
-state_gen!(T1, T2);
-state_gen!(T1, T2, T3);
-state_gen!(T1, T2, T3, T4);
-state_gen!(T1, T2, T3, T4, T5);
-state_gen!(T1, T2, T3, T4, T5, T6);
-state_gen!(T1, T2, T3, T4, T5, T6, T7);
-state_gen!(T1, T2, T3, T4, T5, T6, T7, T8);
+all_tuples!(state_gen);
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::Arc,
+        time::{Duration, Instant},
+    };
+
+    use super::{DuplicatePolicy, FromStateCollector, StateCheck, StateCollector};
+    use crate::prepare_sets::Timing;
+
+    #[test]
+    fn test_shared_fetch() {
+        let mut collector = StateCollector::new();
+        collector.insert(Arc::new(String::from("pool")));
+        collector.insert(1u8);
+
+        let shared = collector.get::<Arc<String>>().unwrap();
+        let (taken, num) = <(Arc<String>, u8)>::fetch_mut(&mut collector).unwrap();
+
+        assert!(Arc::ptr_eq(&shared, &taken));
+        assert_eq!(num, 1);
+        assert!(collector.get::<Arc<String>>().is_err());
+    }
+
+    #[test]
+    fn test_keyed_state() {
+        let mut collector = StateCollector::new();
+        collector.insert_keyed("primary", String::from("primary"));
+        collector.insert_keyed("replica", String::from("replica"));
+        assert!(collector
+            .try_insert_keyed("replica", String::new())
+            .is_err());
+        assert!(DuplicatePolicy::Error.check(&collector).is_ok());
+
+        collector.insert(String::from("default"));
+        collector.insert(String::from("overwrite"));
+        assert_eq!(collector.overwritten(), ["alloc::string::String"]);
+        assert!(DuplicatePolicy::Overwrite.check(&collector).is_ok());
+        assert!(DuplicatePolicy::Error.check(&collector).is_err());
+
+        assert_eq!(
+            collector.take_keyed::<String>("replica").unwrap(),
+            "replica"
+        );
+        assert_eq!(collector.get_keyed::<String>("primary").unwrap(), "primary");
+        assert_eq!(collector.take::<String>().unwrap(), "overwrite");
+        assert!(collector.take_keyed::<String>("replica").is_err());
+    }
+
+    #[test]
+    fn test_state_check() {
+        let mut collector = StateCollector::new();
+        collector.insert(1u8);
+        collector.insert(2u8);
+        collector.insert(Arc::new(1u16));
+        collector.insert_keyed("unused", 1u32);
+
+        let (_,) = <(u8,)>::fetch_mut(&mut collector).unwrap();
+        collector.get::<Arc<u16>>().unwrap();
+
+        assert_eq!(collector.unconsumed(), [r#"u32(key = "unused")"#]);
+        assert!(StateCheck::Ignore.check(&collector).is_ok());
+        assert!(StateCheck::Warn.check(&collector).is_ok());
+        let err = StateCheck::Strict.check(&collector).unwrap_err();
+        assert_eq!(err.overwritten, ["u8"]);
+    }
+
+    #[test]
+    fn test_large_tuple() {
+        let mut collector = StateCollector::new();
+        collector.insert(1u8);
+        collector.insert(2u16);
+        collector.insert(3u32);
+        collector.insert(4u64);
+        collector.insert(5u128);
+        collector.insert(6usize);
+        collector.insert(7i8);
+        collector.insert(8i16);
+        collector.insert(9i32);
+        collector.insert(10i64);
+        collector.insert(11i128);
+        collector.insert(12isize);
+
+        let state = <(
+            u8,
+            u16,
+            u32,
+            u64,
+            u128,
+            usize,
+            i8,
+            i16,
+            i32,
+            i64,
+            i128,
+            isize,
+        )>::fetch_mut(&mut collector)
+        .unwrap();
+        assert_eq!(state, (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12));
+    }
+
+    #[test]
+    fn test_inventory() {
+        let mut collector = StateCollector::new();
+        collector.insert(1u8);
+        collector.produced_by("AddU16", |collector| collector.insert_keyed("port", 2u16));
+        collector.record_prepare(
+            "AddU16",
+            Timing {
+                started: Instant::now(),
+                duration: Duration::from_millis(1),
+            },
+        );
+
+        let inventory = collector.inventory();
+        assert_eq!(inventory.states.len(), 2);
+        assert_eq!(inventory.states[0].name, "u16(key = \"port\")");
+        assert_eq!(inventory.states[0].producer, Some("AddU16"));
+        assert_eq!(inventory.states[1].producer, None);
+        assert_eq!(inventory.prepares[0].name, "AddU16");
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+/// the snapshot of the collected states and the executed prepares, taken before converting
+/// into the State
+#[derive(Debug, Clone, Default)]
+pub struct StateInventory {
+    /// the states in the collector, sorted by name
+    pub states: Vec<StateRecord>,
+    /// the prepares in executed order
+    pub prepares: Vec<PrepareRecord>,
+}
+
+/// a state in the [`StateCollector`](crate::StateCollector)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateRecord {
+    /// the type name of the state, with its key if any
+    pub name: String,
+    /// the type name of the prepare producing it, [None] if inserted directly
+    pub producer: Option<&'static str>,
+}
+
+/// an executed prepare
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrepareRecord {
+    /// the type name of the prepare
+    pub name: &'static str,
+    /// the serial step executing the prepare, the prepares executed concurrently share the same stage
+    pub stage: usize,
+    /// when the prepare started
+    pub started: Instant,
+    /// how long the prepare take, including the [`PrepareDecorator`](crate::PrepareDecorator)
+    pub duration: Duration,
+}
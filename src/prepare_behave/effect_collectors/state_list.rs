@@ -0,0 +1,122 @@
+use std::marker::PhantomData;
+
+use crate::effect_utils::{
+    health::AddHealthCheck,
+    state::{AddKeyedState, AddLazyState, AddState, Lazy},
+    worker::Worker,
+};
+
+/// the empty type level list of state types
+pub struct StateNil;
+
+/// the type level list of state types, with head `H` and tail `T`
+pub struct StateCons<H, T>(PhantomData<(H, T)>);
+
+/// the type level list of state types
+pub trait StateList {
+    /// concat the list `R` after `Self`
+    type Append<R: StateList>: StateList;
+}
+
+impl StateList for StateNil {
+    type Append<R: StateList> = R;
+}
+
+impl<H, T: StateList> StateList for StateCons<H, T> {
+    type Append<R: StateList> = StateCons<H, T::Append<R>>;
+}
+
+/// the state types produced by a [`PrepareStateEffect`](crate::PrepareStateEffect)
+///
+/// using for the compile-time checked state wiring, see [`ServerPrepare::typed_state`](crate::ServerPrepare::typed_state)
+pub trait ProduceState {
+    /// the type level list of state types, like `StateCons<PgPool, StateNil>`
+    type Produced: StateList;
+}
+
+impl<S> ProduceState for AddState<S> {
+    type Produced = StateCons<S, StateNil>;
+}
+
+impl<T> ProduceState for AddLazyState<T> {
+    type Produced = StateCons<Lazy<T>, StateNil>;
+}
+
+/// worker is not a state
+impl<S> ProduceState for Worker<S> {
+    type Produced = StateNil;
+}
+
+/// the [`Health`](crate::health::Health) is always in the collector
+impl ProduceState for AddHealthCheck {
+    type Produced = StateNil;
+}
+
+/// keyed state is not tracked by type
+impl<S> ProduceState for AddKeyedState<S> {
+    type Produced = StateNil;
+}
+
+/// the state types required by a [`FromStateCollector`](crate::FromStateCollector)
+///
+/// it is implemented by [`FromStateCollector`](axum_starter_macro::FromStateCollector) derive,
+/// the keyed fields are not tracked
+pub trait RequireState {
+    /// the type level list of state types
+    type Required: StateList;
+}
+
+/// index of the type in the type level list, the head one
+pub struct Here;
+/// index of the type in the type level list, in the tail
+pub struct There<I>(PhantomData<I>);
+
+/// the type level list contain `T`
+#[diagnostic::on_unimplemented(
+    message = "state `{T}` is not produced by any prepare",
+    label = "missing state `{T}`",
+    note = "adding the prepare producing `{T}` by `prepare_state`, or declaring it by `assume_state`"
+)]
+pub trait Contains<T, I> {}
+
+impl<T, Tail> Contains<T, Here> for StateCons<T, Tail> {}
+
+impl<T, H, Tail, I> Contains<T, There<I>> for StateCons<H, Tail> where Tail: Contains<T, I> {}
+
+/// the type level list contain all types in `R`
+pub trait ContainsAll<R, I> {}
+
+impl<L> ContainsAll<StateNil, StateNil> for L {}
+
+impl<L, H, T, IH, IT> ContainsAll<StateCons<H, T>, StateCons<IH, IT>> for L where
+    L: Contains<H, IH> + ContainsAll<T, IT>
+{
+}
+
+macro_rules! produce_state_gen {
+    ($($id:ident),*$(,)?) => {
+        impl<$($id: ProduceState),*> ProduceState for ($($id,)*) {
+            type Produced = produce_state_gen!(@list $($id,)*);
+        }
+    };
+    (@list) => { StateNil };
+    (@list $head:ident, $($tail:ident,)*) => {
+        <$head::Produced as StateList>::Append<produce_state_gen!(@list $($tail,)*)>
+    };
+}
+
+macro_rules! require_state_gen {
+    ($($id:ident),*$(,)?) => {
+        impl<$($id),*> RequireState for ($($id,)*) {
+            type Required = require_state_gen!(@list $($id,)*);
+        }
+    };
+    (@list) => { StateNil };
+    (@list $head:ident, $($tail:ident,)*) => {
+        StateCons<$head, require_state_gen!(@list $($tail,)*)>
+    };
+}
+
+all_tuples!(produce_state_gen);
+
+all_tuples!(require_state_gen);
@@ -1,10 +1,14 @@
 mod prepare;
 
+use std::any::{type_name, TypeId};
+
 use tower::{
     layer::util::{Identity, Stack},
     ServiceBuilder,
 };
 
+use crate::{phase::MiddlewareOrder, prepare_sets::Timing};
+
 use super::{
     effect_collectors::state_collector::StateCollector,
     traits::{
@@ -31,7 +35,24 @@ impl<R, L> EffectContainer<R, L> {
 }
 
 impl<R, L> EffectContainer<R, L> {
-    pub(crate) fn combine_state(mut self, states: StateCollector) -> Self {
+    /// the stage of the next serial prepare
+    pub(crate) fn stage(&self) -> usize {
+        self.states.stage()
+    }
+
+    pub(crate) fn record_prepare(mut self, name: &'static str, timing: Timing) -> Self {
+        self.states.record_prepare(name, timing);
+        self
+    }
+
+    pub(crate) fn map_state(mut self, map: impl FnOnce(&mut StateCollector)) -> Self {
+        map(&mut self.states);
+        self
+    }
+
+    pub(crate) fn combine_state(mut self, mut states: StateCollector) -> Self {
+        // the prepares executed concurrently are in the same stage
+        self.states.concurrent_stage(&mut states);
         self.states = self.states & states;
         self
     }
@@ -61,6 +82,7 @@ impl EffectContainer<TestRouter, Identity> {
 impl<R, L> EffectContainer<R, L> {
     pub(crate) fn set_middleware<Service, E: PrepareMiddlewareEffect<Service>>(
         self,
+        producer: &'static str,
         effect: E,
     ) -> EffectContainer<R, Stack<E::Middleware, L>> {
         let EffectContainer {
@@ -69,7 +91,14 @@ impl<R, L> EffectContainer<R, L> {
             route,
         } = self;
 
-        let middleware = middleware.layer(effect.take(&mut states));
+        let layer = states.produced_by(producer, |states| {
+            // the effects only adding state, or the `Phased` middleware recorded by itself
+            if TypeId::of::<E::Middleware>() != TypeId::of::<Identity>() {
+                MiddlewareOrder::record(states, type_name::<E::Middleware>());
+            }
+            effect.take(states)
+        });
+        let middleware = middleware.layer(layer);
 
         EffectContainer {
             states,
@@ -78,13 +107,14 @@ impl<R, L> EffectContainer<R, L> {
         }
     }
 
-    pub(crate) fn layer<M>(self, layer: M) -> EffectContainer<R, Stack<M, L>> {
+    pub(crate) fn layer<M: 'static>(self, layer: M) -> EffectContainer<R, Stack<M, L>> {
         let EffectContainer {
-            states,
+            mut states,
             middleware,
             route,
         } = self;
 
+        MiddlewareOrder::record(&mut states, type_name::<M>());
         let middleware = middleware.layer(layer);
 
         EffectContainer {
@@ -94,11 +124,12 @@ impl<R, L> EffectContainer<R, L> {
         }
     }
 
-    pub(crate) fn set_state<E>(mut self, effect: E) -> EffectContainer<R, L>
+    pub(crate) fn set_state<E>(mut self, producer: &'static str, effect: E) -> EffectContainer<R, L>
     where
         E: PrepareStateEffect,
     {
-        effect.take_state(&mut self.states);
+        self.states
+            .produced_by(producer, |states| effect.take_state(states));
         self
     }
 }
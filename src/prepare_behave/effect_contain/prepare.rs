@@ -1,3 +1,4 @@
+use std::any::type_name;
 use std::future::IntoFuture;
 use std::sync::Arc;
 
@@ -6,6 +7,7 @@ use crate::{
         prepare_middleware::PrepareMiddlewareEffect, prepare_route::PrepareRouteEffect,
         prepare_state::PrepareStateEffect, Prepare,
     },
+    prepare_sets::{timed, Mode},
     PrepareDecorator, PrepareError,
 };
 use futures::TryFutureExt;
@@ -28,14 +30,16 @@ impl<R, L> EffectContainer<BaseRouter<R>, L> {
         P::Effect: PrepareRouteEffect<S>,
         S: Clone + Send + 'static + Sync,
     {
-        Ok(self.set_route(
-            prepare
-                .prepare(configure)
-                .into_future()
-                .map_err(|err| PrepareError::to_prepare_error::<P, _>(err))
-                .pipe(|fut| decorator.prepare_decorator::<C, P, _>(fut))
-                .await?,
-        ))
+        let (effect, timing) = prepare
+            .prepare(configure)
+            .into_future()
+            .map_err(|err| PrepareError::to_prepare_error::<P, _>(err))
+            .pipe(|fut| decorator.prepare_decorator::<C, P, _>(fut))
+            .pipe(|fut| timed(fut, type_name::<P>(), Mode::Serial(self.stage())))
+            .await;
+        Ok(self
+            .record_prepare(type_name::<P>(), timing)
+            .set_route(effect?))
     }
 }
 
@@ -52,14 +56,16 @@ impl<R, L> EffectContainer<R, L> {
         P: Prepare<C>,
         P::Effect: PrepareStateEffect,
     {
-        Ok(self.set_state(
-            prepare
-                .prepare(configure)
-                .into_future()
-                .map_err(PrepareError::to_prepare_error::<P, _>)
-                .pipe(|fut| decorator.prepare_decorator::<C, P, _>(fut))
-                .await?,
-        ))
+        let (effect, timing) = prepare
+            .prepare(configure)
+            .into_future()
+            .map_err(PrepareError::to_prepare_error::<P, _>)
+            .pipe(|fut| decorator.prepare_decorator::<C, P, _>(fut))
+            .pipe(|fut| timed(fut, type_name::<P>(), Mode::Serial(self.stage())))
+            .await;
+        Ok(self
+            .record_prepare(type_name::<P>(), timing)
+            .set_state(type_name::<P>(), effect?))
     }
 
     pub(crate) async fn then_middleware<D, S, C, P>(
@@ -77,13 +83,15 @@ impl<R, L> EffectContainer<R, L> {
         P: Prepare<C>,
         P::Effect: PrepareMiddlewareEffect<S>,
     {
-        Ok(self.set_middleware(
-            prepare
-                .prepare(configure)
-                .into_future()
-                .map_err(PrepareError::to_prepare_error::<P, _>)
-                .pipe(|fut| decorator.prepare_decorator::<C, P, _>(fut))
-                .await?,
-        ))
+        let (effect, timing) = prepare
+            .prepare(configure)
+            .into_future()
+            .map_err(PrepareError::to_prepare_error::<P, _>)
+            .pipe(|fut| decorator.prepare_decorator::<C, P, _>(fut))
+            .pipe(|fut| timed(fut, type_name::<P>(), Mode::Serial(self.stage())))
+            .await;
+        Ok(self
+            .record_prepare(type_name::<P>(), timing)
+            .set_middleware(type_name::<P>(), effect?))
     }
 }
@@ -31,12 +31,4 @@ macro_rules! route_effect {
         }
     };
 }
-route_effect!();
-route_effect!(T1);
-route_effect!(T1, T2);
-route_effect!(T1, T2, T3);
-route_effect!(T1, T2, T3, T4);
-route_effect!(T1, T2, T3, T4, T5);
-route_effect!(T1, T2, T3, T4, T5, T6);
-route_effect!(T1, T2, T3, T4, T5, T6, T7);
-route_effect!(T1, T2, T3, T4, T5, T6, T7, T8);
+all_tuples!(route_effect);
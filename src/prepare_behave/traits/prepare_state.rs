@@ -3,7 +3,6 @@ use crate::prepare_behave::effect_collectors::state_collector::StateCollector;
 /// Prepare for Global State
 ///
 /// for instance the Connection Pool of Database
-
 pub trait PrepareStateEffect: 'static {
     fn take_state(self, states: &mut StateCollector);
 }
@@ -29,12 +28,4 @@ macro_rules! state_effect {
     };
 }
 
-state_effect!();
-state_effect!(T1);
-state_effect!(T1, T2);
-state_effect!(T1, T2, T3);
-state_effect!(T1, T2, T3, T4);
-state_effect!(T1, T2, T3, T4, T5);
-state_effect!(T1, T2, T3, T4, T5, T6);
-state_effect!(T1, T2, T3, T4, T5, T6, T7);
-state_effect!(T1, T2, T3, T4, T5, T6, T7, T8);
+all_tuples!(state_effect);
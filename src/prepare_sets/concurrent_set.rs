@@ -1,4 +1,3 @@
-#[allow(unused_imports)]
 use std::any::type_name;
 use std::{future::IntoFuture, sync::Arc};
 
@@ -17,7 +16,7 @@ use crate::{
     PrepareError,
 };
 
-use super::{BoxFuture, StateContainerFuture, StateContainerResult};
+use super::{timed, BoxFuture, Mode, StateContainerFuture, StateContainerResult};
 
 /// apply all [Prepare](Prepare) task concurrently
 ///
@@ -62,13 +61,15 @@ where
                 .prepare(configure)
                 .into_future()
                 .map_err(PrepareError::to_prepare_error::<P, _>)
-                .pipe(|fut| self.decorator.prepare_decorator::<C, P, _>(fut)),
+                .pipe(|fut| self.decorator.prepare_decorator::<C, P, _>(fut))
+                .pipe(|fut| timed(fut, type_name::<P>(), Mode::Concurrent)),
         )
-        .map(|(l, r)| {
+        .map(|(l, (r, timing))| {
             Ok({
                 let mut states = l?;
                 let effect = r?;
-                effect.take_state(&mut states);
+                states.record_prepare(type_name::<P>(), timing);
+                states.produced_by(type_name::<P>(), |states| effect.take_state(states));
 
                 states
             })
@@ -100,11 +101,14 @@ where
                 .prepare(configure)
                 .into_future()
                 .map_err(PrepareError::to_prepare_error::<P, _>)
-                .pipe(|fut| self.decorator.prepare_decorator::<C, P, _>(fut)),
+                .pipe(|fut| self.decorator.prepare_decorator::<C, P, _>(fut))
+                .pipe(|fut| timed(fut, type_name::<P>(), Mode::Concurrent)),
         )
-        .map(|(l, r)| {
+        .map(|(l, (r, timing))| {
             r?;
-            l
+            let mut states = l?;
+            states.record_prepare(type_name::<P>(), timing);
+            Ok(states)
         })
         .boxed_local();
 
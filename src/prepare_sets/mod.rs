@@ -1,4 +1,7 @@
-use std::pin::Pin;
+use std::{
+    pin::Pin,
+    time::{Duration, Instant},
+};
 
 use futures::Future;
 
@@ -15,3 +18,165 @@ pub type ContainerFuture<R, L> = BoxFuture<ContainerResult<R, L>>;
 
 pub type StateContainerResult = Result<StateCollector, PrepareError>;
 pub type StateContainerFuture = BoxFuture<StateContainerResult>;
+
+/// when a prepare started and how long it take
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Timing {
+    pub(crate) started: Instant,
+    pub(crate) duration: Duration,
+}
+
+/// how the prepare is executed
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(not(feature = "logger"), allow(dead_code))]
+pub(crate) enum Mode {
+    /// executed serially in the stage
+    Serial(usize),
+    /// executed concurrently, the stage is recorded by the span of the concurrent set
+    Concurrent,
+}
+
+/// execute the prepare `name` and measure how long it take
+///
+/// with `logger` feature, the prepare is executed inside its own span
+pub(crate) async fn timed<F, T>(fut: F, name: &'static str, mode: Mode) -> (F::Output, Timing)
+where
+    F: Future<Output = Result<T, PrepareError>>,
+{
+    #[cfg(feature = "logger")]
+    let span = {
+        let (mode, stage) = match mode {
+            Mode::Serial(stage) => ("serial", Some(stage)),
+            Mode::Concurrent => ("concurrent", None),
+        };
+        tracing::info_span!(
+            "prepare",
+            otel.name = name,
+            otel.status_code = tracing::field::Empty,
+            prepare = name,
+            stage,
+            mode,
+            outcome = tracing::field::Empty,
+            error = tracing::field::Empty,
+        )
+    };
+    #[cfg(feature = "logger")]
+    let fut = tracing::Instrument::instrument(fut, span.clone());
+    #[cfg(not(feature = "logger"))]
+    let _ = (name, mode);
+
+    let started = Instant::now();
+    let output = fut.await;
+    let timing = Timing {
+        started,
+        duration: started.elapsed(),
+    };
+
+    #[cfg(feature = "logger")]
+    match &output {
+        Ok(_) => {
+            span.record("outcome", "ok");
+            span.record("otel.status_code", "OK");
+        }
+        Err(err) => {
+            span.record("outcome", "error");
+            span.record("otel.status_code", "ERROR");
+            span.record("error", tracing::field::display(err));
+        }
+    }
+    (output, timing)
+}
+
+#[cfg(all(test, feature = "logger"))]
+mod test {
+    use std::{
+        collections::HashMap,
+        fmt::Debug,
+        sync::{Arc, Mutex},
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+        Event, Metadata, Subscriber,
+    };
+
+    use super::{timed, Mode};
+    use crate::PrepareError;
+
+    /// the fields of each span, in created order
+    #[derive(Clone, Default)]
+    struct Capture(Arc<Mutex<Vec<Fields>>>);
+
+    #[derive(Default)]
+    struct Fields(HashMap<&'static str, String>);
+
+    impl Visit for Fields {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            self.0.insert(field.name(), value.to_owned());
+        }
+
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            self.0.insert(field.name(), format!("{value:?}"));
+        }
+    }
+
+    impl Subscriber for Capture {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut spans = self.0.lock().unwrap();
+            let mut fields = Fields::default();
+            span.record(&mut fields);
+            spans.push(fields);
+            Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut spans = self.0.lock().unwrap();
+            values.record(&mut spans[span.into_u64() as usize - 1]);
+        }
+
+        fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &Id) {}
+
+        fn exit(&self, _: &Id) {}
+    }
+
+    #[tokio::test]
+    async fn test_prepare_span() {
+        let capture = Capture::default();
+        let _guard = tracing::subscriber::set_default(capture.clone());
+
+        let (output, _) = timed(async { Ok(()) }, "Foo", Mode::Serial(1)).await;
+        assert!(output.is_ok());
+        let (output, _) = timed(
+            async { Err::<(), _>(PrepareError::new("Bar", "no connection".into())) },
+            "Bar",
+            Mode::Concurrent,
+        )
+        .await;
+        assert!(output.is_err());
+
+        let spans = capture.0.lock().unwrap();
+        let field = |idx: usize, name| spans[idx].0.get(name).map(String::as_str);
+        assert_eq!(field(0, "prepare"), Some("Foo"));
+        assert_eq!(field(0, "stage"), Some("1"));
+        assert_eq!(field(0, "mode"), Some("serial"));
+        assert_eq!(field(0, "outcome"), Some("ok"));
+        assert_eq!(field(0, "otel.status_code"), Some("OK"));
+
+        assert_eq!(field(1, "prepare"), Some("Bar"));
+        // not in a serial stage
+        assert_eq!(field(1, "stage"), None);
+        assert_eq!(field(1, "mode"), Some("concurrent"));
+        assert_eq!(field(1, "outcome"), Some("error"));
+        assert_eq!(field(1, "otel.status_code"), Some("ERROR"));
+        assert!(field(1, "error").unwrap().contains("no connection"));
+    }
+}
@@ -11,12 +11,12 @@ use crate::server_prepare::PrepareDecorator;
 use crate::{
     prepare_behave::{
         effect_traits::{Prepare, PrepareMiddlewareEffect, PrepareRouteEffect, PrepareStateEffect},
-        EffectContainer,
+        EffectContainer, StateCollector,
     },
     ConcurrentPrepareSet, PrepareError,
 };
 
-use super::{BoxFuture, ContainerFuture, ContainerResult};
+use super::{timed, BoxFuture, ContainerFuture, ContainerResult, Mode};
 
 /// a set of [Prepare] task executing one by one
 ///
@@ -197,7 +197,10 @@ where
                     .into_future()
                     .map_err(|err| PrepareError::to_prepare_error::<P, _>(err))
                     .pipe(move |fut| decorator.prepare_decorator::<C, P, _>(fut))
-                    .map_ok(|_| collect)
+                    .pipe(|fut| timed(fut, type_name::<P>(), Mode::Serial(collect.stage())))
+                    .map(|(ret, timing)| {
+                        ret.map(|_| collect.record_prepare(type_name::<P>(), timing))
+                    })
             })
             .boxed_local();
 
@@ -223,6 +226,21 @@ where
         }
     }
 
+    /// apply on the collector after the prepares added before
+    pub(crate) fn map_state<F>(self, map: F) -> Self
+    where
+        F: FnOnce(&mut StateCollector) + 'static,
+    {
+        SerialPrepareSet {
+            prepare_fut: self
+                .prepare_fut
+                .map_ok(|effect| effect.map_state(map))
+                .boxed_local(),
+            configure: self.configure,
+            decorator: self.decorator,
+        }
+    }
+
     /// combine concurrent set into self
     pub(crate) fn combine(
         self,
@@ -232,7 +250,20 @@ where
 
         let prepare_fut = self
             .prepare_fut
-            .and_then(|container| fut.map_ok(|states| container.combine_state(states)))
+            .and_then(|container| {
+                fut.pipe(|fut| {
+                    #[cfg(feature = "logger")]
+                    {
+                        let span = tracing::info_span!("concurrent", stage = container.stage());
+                        tracing::Instrument::instrument(fut, span)
+                    }
+                    #[cfg(not(feature = "logger"))]
+                    {
+                        fut
+                    }
+                })
+                .map_ok(|states| container.combine_state(states))
+            })
             .boxed_local();
 
         SerialPrepareSet {
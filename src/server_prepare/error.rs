@@ -1,7 +1,12 @@
 use std::fmt::{Debug, Display, Formatter};
 use std::{any::type_name, error};
 
-use crate::prepare_behave::effect_collectors::state_collector::TypeNotInState;
+use crate::{
+    effect_utils::worker::UnclaimedWorker,
+    prepare_behave::effect_collectors::state_collector::{
+        DuplicateState, TypeNotInState, UncheckedState,
+    },
+};
 
 #[derive(thiserror::Error)]
 /// the error while prepare for each [Prepare](crate::Prepare) task
@@ -25,11 +30,57 @@ impl PrepareError {
             source: src,
         }
     }
+    /// the type name of the failed prepare
+    pub fn prepare(&self) -> &'static str {
+        self.ty
+    }
     pub fn to_prepare_error<P, E: error::Error + 'static>(err: E) -> PrepareError {
         PrepareError::new(type_name::<P>(), Box::new(err))
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+/// the error of [Prepare](crate::Prepare) generated by [`prepare`](axum_starter_macro::prepare)
+/// with fallible arguments
+pub enum PrepareArgError<E: error::Error + 'static> {
+    #[error("provide argument `{arg}` failure : {source}")]
+    /// fallible argument provide error
+    Provide {
+        arg: &'static str,
+        source: Box<dyn error::Error>,
+    },
+    #[error(transparent)]
+    /// the prepare task error
+    Prepare(E),
+}
+
+impl<E: error::Error + 'static> PrepareArgError<E> {
+    pub fn provide<Err: error::Error + 'static>(arg: &'static str, err: Err) -> Self {
+        Self::Provide {
+            arg,
+            source: Box::new(err),
+        }
+    }
+}
+
+/// mapping the return type of prepare function to [`Result<_, PrepareArgError<_>>`]
+#[doc(hidden)]
+pub trait IntoPrepareResult {
+    type Effect;
+    type Error: error::Error + 'static;
+
+    fn into_prepare_result(self) -> Result<Self::Effect, PrepareArgError<Self::Error>>;
+}
+
+impl<T, E: error::Error + 'static> IntoPrepareResult for Result<T, E> {
+    type Effect = T;
+    type Error = E;
+
+    fn into_prepare_result(self) -> Result<Self::Effect, PrepareArgError<Self::Error>> {
+        self.map_err(PrepareArgError::Prepare)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 /// error during the [ServerPrepare::prepare_start](super::ServerPrepare::prepare_start)
 pub enum PrepareStartError {
@@ -40,5 +91,14 @@ pub enum PrepareStartError {
     /// state convent error
     State(#[from] TypeNotInState),
     #[error(transparent)]
+    /// state inserted more than once with [`DuplicatePolicy::Error`](crate::DuplicatePolicy::Error)
+    DuplicateState(#[from] DuplicateState),
+    #[error(transparent)]
+    /// state check failure with [`StateCheck::Strict`](crate::StateCheck::Strict)
+    UncheckedState(#[from] UncheckedState),
+    #[error(transparent)]
+    /// the [`Worker`](crate::worker::Worker) require a State type other than the State
+    UnclaimedWorker(#[from] UnclaimedWorker),
+    #[error(transparent)]
     IO(#[from] std::io::Error),
 }
@@ -4,10 +4,13 @@ use std::marker::PhantomData;
 
 use crate::SerialPrepareSet;
 
-pub use self::error::{PrepareError, PrepareStartError};
+pub use self::error::{IntoPrepareResult, PrepareArgError, PrepareError, PrepareStartError};
+pub use self::start_process::boot_report::{BootReport, PrepareTiming};
 pub use self::start_process::configure::{
     BindServe, EmptyDecorator, LoggerInitialization, PrepareDecorator, ServeAddress,
+    TimingDecorator,
 };
+pub use self::start_process::schedule::Schedule;
 use self::start_process::{
     graceful_shutdown::NoGraceful, logger::LogInit, state_ready::StateNotReady,
 };
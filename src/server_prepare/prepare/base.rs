@@ -1,29 +1,34 @@
-use std::{convert::Infallible, future::IntoFuture, io, sync::Arc};
+use std::{convert::Infallible, future::IntoFuture, io, net::SocketAddr, sync::Arc, time::Instant};
 
 use axum::{
     body::{Body, Bytes},
     routing::Route,
     BoxError, Router,
 };
-use futures::Future;
+use futures::{future::join_all, Future, FutureExt};
 use hyper::{Request, Response};
 use tap::Pipe;
 use tokio::spawn;
 use tower::{layer::util::Identity, Layer, Service, ServiceBuilder};
 
 use crate::{
+    config_provide::profile::ReportProfile,
+    effect_utils::{
+        phase::{layer_router, MiddlewareOrder},
+        worker::WorkerSupervisor,
+    },
     prepare_behave::effect_contain::BaseRouter,
     prepare_sets::ContainerResult,
     server_prepare::{
         start_process::{
             graceful_shutdown::{FetchGraceful, NoGraceful},
             logger::NoLog,
-            state_ready::{StateNotReady, StateReady},
+            state_ready::{StateNotReady, StateReady, StateWiring},
         },
         EmptyDecorator,
     },
-    BindServe, FromStateCollector, PrepareRouteEffect, PrepareStartError, SerialPrepareSet,
-    ServerPrepare, ServerReady,
+    BindServe, BootReport, FromStateCollector, PrepareRouteEffect, PrepareStartError,
+    SerialPrepareSet, ServerPrepare, ServerReady,
 };
 
 impl<C: 'static>
@@ -51,13 +56,23 @@ impl<C: 'static>
     }
 }
 
-impl<C: 'static, Log, State, Graceful, R, L, Decorator>
-    ServerPrepare<C, ContainerResult<BaseRouter<R>, L>, Log, StateReady<State>, Graceful, Decorator>
+impl<C: 'static, Log, State, W, Graceful, R, L, Decorator>
+    ServerPrepare<
+        C,
+        ContainerResult<BaseRouter<R>, L>,
+        Log,
+        StateReady<State, W>,
+        Graceful,
+        Decorator,
+    >
 {
     /// prepare to start this server
     ///
     /// this will consume `Self` then return [ServerReady](ServerReady)
-    pub async fn preparing<NewResBody>(
+    ///
+    /// `I` is the type level index of the state wiring, see [`ServerPrepare::typed_state`],
+    /// it is always inferred, using `_` for it with turbofish, like `preparing::<_, _>()`
+    pub async fn preparing<NewResBody, I>(
         self,
     ) -> Result<
         ServerReady<
@@ -84,23 +99,76 @@ impl<C: 'static, Log, State, Graceful, R, L, Decorator>
         // state
         State: FromStateCollector,
         State: Clone + Send + 'static + Sync,
+        W: StateWiring<State, I>,
+        // graceful
+        Graceful: FetchGraceful,
+    {
+        self.preparing_with_report().await.map(|(ready, _)| ready)
+    }
+
+    /// prepare to start this server, with the [BootReport] of how long each prepare take
+    ///
+    /// the report is also logged at info level (with `logger` feature)
+    ///
+    /// `I` is inferred as [`ServerPrepare::preparing`]
+    pub async fn preparing_with_report<NewResBody, I>(
+        self,
+    ) -> Result<
+        (
+            ServerReady<
+                impl Future<Output = Result<(), io::Error>>,
+                impl Future<Output = Result<(), io::Error>>,
+            >,
+            BootReport,
+        ),
+        PrepareStartError,
+    >
+    where
+        // config
+        C: BindServe,
+        // middleware
+        L: Send + 'static,
+        ServiceBuilder<L>: Layer<Route> + Clone,
+        <ServiceBuilder<L> as Layer<Route>>::Service: Send
+            + Clone
+            + Service<Request<Body>, Response = Response<NewResBody>, Error = Infallible>
+            + 'static,
+        <<ServiceBuilder<L> as Layer<Route>>::Service as Service<Request<Body>>>::Future: Send,
+        NewResBody: http_body::Body<Data = Bytes> + Send + 'static,
+        NewResBody::Error: Into<BoxError>,
+        // prepare task
+        R: PrepareRouteEffect<State>,
+        // state
+        State: FromStateCollector,
+        State: Clone + Send + 'static + Sync,
+        W: StateWiring<State, I>,
         // graceful
         Graceful: FetchGraceful,
     {
         async {
+            let boot = Instant::now();
             let (prepare_fut, configure) = self.prepares.unwrap();
             debug!(execute = "Prepare");
 
-            let (state, middleware, BaseRouter(route)) = prepare_fut.await?.unwrap();
+            let prepared = prepare_fut.await;
+            #[cfg(feature = "metrics")]
+            if let Err(err) = &prepared {
+                crate::metrics::record_prepare_failure(err);
+            }
+            let (mut state, middleware, BaseRouter(route)) = prepared?.unwrap();
+            let profile = ReportProfile::take(&mut state);
+            let order = MiddlewareOrder::take_with_builtin(&mut state);
+            let layers = order.layers();
 
-            let state = State::fetch(state)?;
+            let prepares = state.inventory().prepares;
+            let (state, workers, health) = self.state.fetch_state(state)?;
 
             debug!(effect = "Router");
             let router = Router::new()
                 // apply to prepare effect on router
                 .pipe(|router| route.set_route(router))
-                // adding middleware
-                .pipe(|router| router.layer(middleware))
+                // adding middleware, the phased ones around the middleware stack
+                .pipe(|router| layer_router(router, middleware, order.into_layers()))
                 .with_state(state.clone());
 
             debug!(effect = "Graceful Shutdown");
@@ -108,32 +176,58 @@ impl<C: 'static, Log, State, Graceful, R, L, Decorator>
 
             debug!(effect = "Server");
             let listener = configure.bind().await?;
-            let server = axum::serve(listener, router);
+            // the peer address for `ConnectInfo`
+            let server = axum::serve(
+                listener,
+                router.into_make_service_with_connect_info::<SocketAddr>(),
+            );
             debug!(effect = "All Done");
             info!(
                 service.address = %&configure.get_address().into(),
+                config.profile = profile.as_ref().map(tracing::field::display),
                 service.status = "Ready"
             );
-            let post_prepare_tasks = self.state.take();
+            let report = BootReport::new(&prepares, layers, profile, boot);
+            info!("Boot Report\n{report}");
+            #[cfg(feature = "metrics")]
+            crate::metrics::record_boot(&report);
+            let (post_prepare_tasks, schedules) = self.state.take();
             debug!(
                 execute = "Post Prepare Tasks",
                 numbers = post_prepare_tasks.len()
             );
-            for task in post_prepare_tasks {
-                spawn({
-                    let local_state = state.clone();
-                    async move {
-                        (task)(local_state).await;
-                    }
-                });
+            let tasks = post_prepare_tasks
+                .into_iter()
+                .map(|task| spawn((task)(state.clone())))
+                .collect::<Vec<_>>();
+            // ready after all post prepare tasks finished
+            spawn({
+                let health = health.clone();
+                async move {
+                    join_all(tasks).await;
+                    health.set_ready(true);
+                }
+            });
+
+            debug!(execute = "Workers");
+            let mut supervisor = WorkerSupervisor::spawn(workers, &state);
+            debug!(execute = "Scheduled Jobs", numbers = schedules.len());
+            for schedule in schedules {
+                let local_state = state.clone();
+                supervisor.spawn_task(move |shutdown| schedule(local_state, shutdown));
             }
 
-            Ok(match graceful {
+            let ready = match graceful {
                 Some(fut) => {
-                    ServerReady::Graceful(server.with_graceful_shutdown(fut).into_future())
+                    // not ready as soon as the graceful shutdown signal arrive
+                    let signal = supervisor.shutdown_on(fut.map(move |_| health.set_ready(false)));
+                    ServerReady::Graceful(
+                        supervisor.run(server.with_graceful_shutdown(signal).into_future()),
+                    )
                 }
-                None => ServerReady::Server(server.into_future()),
-            })
+                None => ServerReady::Server(supervisor.run(server.into_future())),
+            };
+            Ok((ready, report))
         }
         .pipe(|fut| {
             #[cfg(feature = "logger")]
@@ -6,19 +6,22 @@ use axum::{
     response::Response,
     BoxError,
 };
+use futures::future::join_all;
 use http::Request;
 use tap::Pipe;
 use tokio::spawn;
-use tower::{layer::util::Identity, util::MapResponseLayer, Layer, Service, ServiceBuilder};
+use tower::{layer::util::Identity, util::MapResponseLayer, Layer, Service};
 
 use crate::{
+    config_provide::profile::ReportProfile,
+    phase::{layer_service, MiddlewareOrder, PhasedService},
     prepare_behave::effect_contain::TestRouter,
     prepare_sets::ContainerResult,
     server_prepare::{
         start_process::{
             graceful_shutdown::NoGraceful,
             logger::NoLog,
-            state_ready::{StateNotReady, StateReady},
+            state_ready::{StateNotReady, StateReady, StateWiring},
         },
         EmptyDecorator,
     },
@@ -51,13 +54,20 @@ impl<C: 'static>
     }
 }
 
-impl<C: 'static, Log, State, Graceful, L, Decorator>
-    ServerPrepare<C, ContainerResult<TestRouter, L>, Log, StateReady<State>, Graceful, Decorator>
+impl<C: 'static, Log, State, W, Graceful, L, Decorator>
+    ServerPrepare<C, ContainerResult<TestRouter, L>, Log, StateReady<State, W>, Graceful, Decorator>
 {
     /// prepare to a service for test
     ///
     /// this will consume `Self` then return a [Service](tower::Service) for the following test
-    pub async fn preparing_test<NewResBody, H, T>(
+    ///
+    /// ## Note
+    /// the middleware is in the same order as [`ServerPrepare::preparing`], thus the middleware stack wraps
+    /// the [`Phase::Innermost`](crate::phase::Phase::Innermost) middleware around the handler, that is the [PhasedService]
+    ///
+    /// `I` is inferred as [`ServerPrepare::preparing`](crate::ServerPrepare::preparing),
+    /// using `_` for it with turbofish, like `preparing_test::<_, _, _, _>(handler)`
+    pub async fn preparing_test<NewResBody, H, T, I>(
         self,
         handler: H,
     ) -> Result<
@@ -67,46 +77,52 @@ impl<C: 'static, Log, State, Graceful, L, Decorator>
     where
         // middleware
         L: Send + 'static,
-        L: Layer<HandlerService<H, T, State>>,
+        L: Layer<PhasedService<HandlerService<H, T, State>>>,
         L::Service: Service<Request<Body>, Response = Response<NewResBody>, Error = Infallible>
             + Send
             + Clone
             + 'static,
+        <L::Service as Service<Request<Body>>>::Future: Send + 'static,
         NewResBody: http_body::Body<Data = Bytes> + Send + 'static,
         NewResBody::Error: Into<BoxError>,
         // state
         State: FromStateCollector,
         State: Clone + Send + 'static + Sync,
+        W: StateWiring<State, I>,
         // handler
         H: Handler<T, State>,
+        T: 'static,
     {
         async {
             let (prepare_fut, _) = self.prepares.unwrap();
             debug!(execute = "Prepare");
 
-            let (state, middleware, _) = prepare_fut.await?.unwrap();
+            let (mut state, middleware, _) = prepare_fut.await?.unwrap();
+            // the profile is only reported when serving
+            ReportProfile::take(&mut state);
+            let layers = MiddlewareOrder::take_with_builtin(&mut state).into_layers();
 
-            let state = State::fetch(state)?;
+            // the workers are not run in test
+            let (state, _, health) = self.state.fetch_state(state)?;
 
-            let post_prepare_tasks = self.state.take();
+            // the scheduled jobs are not run in test
+            let (post_prepare_tasks, _) = self.state.take();
             debug!(
                 execute = "Post Prepare Tasks",
                 numbers = post_prepare_tasks.len()
             );
-            for task in post_prepare_tasks {
-                spawn({
-                    let local_state = state.clone();
-                    async move {
-                        (task)(local_state).await;
-                    }
-                });
-            }
+            let tasks = post_prepare_tasks
+                .into_iter()
+                .map(|task| spawn((task)(state.clone())))
+                .collect::<Vec<_>>();
+            spawn(async move {
+                join_all(tasks).await;
+                health.set_ready(true);
+            });
 
-            let service = handler.with_state(state);
-            Ok(ServiceBuilder::new()
-                .layer(MapResponseLayer::new(TestResponse::new))
-                .layer(middleware)
-                .service(service))
+            // the phased middleware around the middleware stack, the same as `preparing`
+            let service = layer_service(handler.with_state(state), middleware, layers);
+            Ok(MapResponseLayer::new(TestResponse::new).layer(service))
         }
         .pipe(|fut| {
             #[cfg(feature = "logger")]
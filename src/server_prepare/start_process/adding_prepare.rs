@@ -2,6 +2,8 @@ use tower::layer::util::Stack;
 
 use crate::prepare_behave::effect_contain::BaseRouter;
 use crate::server_prepare::PrepareDecorator;
+
+use super::state_ready::TrackState;
 use crate::{
     prepare_behave::effect_traits::{
         Prepare, PrepareMiddlewareEffect, PrepareRouteEffect, PrepareStateEffect,
@@ -19,6 +21,15 @@ type ServerPrepareNestRoute<C, P, Ri, Li, Log, State, Graceful, Decorator> = Ser
     Decorator,
 >;
 
+type ServerPrepareTrackState<C, P, Ri, Li, Log, State, Graceful, Decorator> = ServerPrepare<
+    C,
+    ContainerResult<Ri, Li>,
+    Log,
+    <State as TrackState<<P as Prepare<C>>::Effect>>::Tracked,
+    Graceful,
+    Decorator,
+>;
+
 type ServerPrepareNestMiddleware<C, P, Ri, Li, S, Log, State, Graceful, Decorator> = ServerPrepare<
     C,
     ContainerResult<
@@ -104,10 +115,11 @@ where
     pub fn prepare_state<P>(
         self,
         prepare: P,
-    ) -> ServerPrepare<C, ContainerResult<Ri, Li>, Log, State, Graceful, Decorator>
+    ) -> ServerPrepareTrackState<C, P, Ri, Li, Log, State, Graceful, Decorator>
     where
         P: Prepare<C> + 'static,
         P::Effect: PrepareStateEffect,
+        State: TrackState<P::Effect>,
     {
         let prepares = self.span.in_scope(|| {
             debug!(
@@ -118,7 +130,7 @@ where
             self.prepares.then_state(prepare)
         });
 
-        ServerPrepare::new(prepares, self.graceful, self.state, self.span)
+        ServerPrepare::new(prepares, self.graceful, self.state.track(), self.span)
     }
 
     /// adding a [Prepare] apply  effect on **State** and **Middleware**
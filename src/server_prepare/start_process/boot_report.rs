@@ -0,0 +1,235 @@
+use std::{
+    fmt::{Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config_provide::profile::ReportProfile, phase::LayerInfo, prepare_sets::ContainerResult,
+    server_prepare::PrepareDecorator, PrepareRecord, Profile, Provider, ServerPrepare,
+};
+
+impl<C: 'static, Log, State, Graceful, R: 'static, L: 'static, Decorator>
+    ServerPrepare<C, ContainerResult<R, L>, Log, State, Graceful, Decorator>
+where
+    Decorator: PrepareDecorator,
+{
+    /// report the [Profile] provided by the config in the `Ready` log and the [BootReport]
+    ///
+    /// the config is loaded before the logger initialized, thus the log of
+    /// [`ConfigProfiles::load`](crate::ConfigProfiles::load) is normally lost
+    pub fn report_profile(self) -> Self
+    where
+        C: for<'r> Provider<'r, Profile>,
+    {
+        let profile = ReportProfile::new(self.prepares.get_ref_configure().provide());
+        ServerPrepare::new(
+            self.prepares
+                .map_state(move |states| *states.aggregate::<ReportProfile>() = profile),
+            self.graceful,
+            self.state,
+            self.span,
+        )
+    }
+}
+
+/// the timing of a prepare in the [BootReport]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrepareTiming {
+    /// the type name of the prepare
+    pub name: &'static str,
+    /// the serial step executing the prepare, the prepares executed concurrently share the same stage
+    pub stage: usize,
+    /// when the prepare started, since `preparing` called
+    pub offset: Duration,
+    /// how long the prepare take
+    pub duration: Duration,
+}
+
+/// how long each prepare take during `preparing`, returned by [`ServerPrepare::preparing_with_report`](crate::ServerPrepare::preparing_with_report)
+/// and logged at info level
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BootReport {
+    /// the config profile, with [`ServerPrepare::report_profile`]
+    pub profile: Option<Profile>,
+    /// the prepares in started order
+    pub prepares: Vec<PrepareTiming>,
+    /// the middleware in the final order, outermost first
+    pub middleware: Vec<LayerInfo>,
+    /// from `preparing` called to the server ready
+    pub total: Duration,
+}
+
+impl BootReport {
+    pub(crate) fn new(
+        records: &[PrepareRecord],
+        middleware: Vec<LayerInfo>,
+        profile: Option<Profile>,
+        boot: Instant,
+    ) -> Self {
+        let mut prepares = records
+            .iter()
+            .map(|record| PrepareTiming {
+                name: record.name,
+                stage: record.stage,
+                offset: record.started.saturating_duration_since(boot),
+                duration: record.duration,
+            })
+            .collect::<Vec<_>>();
+        prepares.sort_by_key(|prepare| (prepare.stage, prepare.offset));
+        Self {
+            profile,
+            prepares,
+            middleware,
+            total: boot.elapsed(),
+        }
+    }
+
+    /// the slowest prepare of each stage, the stages are executed one by one,
+    /// thus speeding up them shortens the boot
+    pub fn critical_path(&self) -> Vec<&PrepareTiming> {
+        let mut path: Vec<&PrepareTiming> = Vec::new();
+        for prepare in &self.prepares {
+            match path.last_mut() {
+                Some(slowest) if slowest.stage == prepare.stage => {
+                    if prepare.duration > slowest.duration {
+                        *slowest = prepare;
+                    }
+                }
+                _ => path.push(prepare),
+            }
+        }
+        path
+    }
+}
+
+impl Display for BootReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let critical = self.critical_path();
+        let critical_total = critical
+            .iter()
+            .map(|prepare| prepare.duration)
+            .sum::<Duration>();
+        writeln!(
+            f,
+            "boot in {:?}, critical path {critical_total:?}",
+            self.total
+        )?;
+        if let Some(profile) = &self.profile {
+            writeln!(f, "profile {profile}")?;
+        }
+        writeln!(
+            f,
+            "{:>5} {:>12} {:>12}   prepare",
+            "stage", "offset", "duration"
+        )?;
+        for prepare in &self.prepares {
+            let mark = if critical.iter().any(|c| std::ptr::eq(*c, prepare)) {
+                "*"
+            } else {
+                " "
+            };
+            writeln!(
+                f,
+                "{:>5} {:>12} {:>12} {mark} {}",
+                prepare.stage,
+                format!("{:?}", prepare.offset),
+                format!("{:?}", prepare.duration),
+                prepare.name
+            )?;
+        }
+        if !self.middleware.is_empty() {
+            writeln!(f, "{:>10}   middleware (outermost first)", "phase")?;
+            for layer in &self.middleware {
+                // the prepare name is shorter than the layer type name
+                let name = layer.prepare.unwrap_or(layer.name);
+                writeln!(f, "{:>10}   {name}", format!("{:?}", layer.phase))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::BootReport;
+    use crate::{
+        config_provide::profile::ReportProfile, ConfigProfiles, PrepareRecord, Profile, Provider,
+        ServerPrepare,
+    };
+
+    #[derive(Default)]
+    struct Conf(Profile);
+
+    impl AsMut<Profile> for Conf {
+        fn as_mut(&mut self) -> &mut Profile {
+            &mut self.0
+        }
+    }
+
+    impl<'r> Provider<'r, Profile> for Conf {
+        fn provide(&'r self) -> Profile {
+            self.0.clone()
+        }
+    }
+
+    #[test]
+    fn test_critical_path() {
+        let boot = Instant::now();
+        let record = |name, stage, offset, duration| PrepareRecord {
+            name,
+            stage,
+            started: boot + Duration::from_millis(offset),
+            duration: Duration::from_millis(duration),
+        };
+        let report = BootReport::new(
+            &[
+                record("Config", 0, 0, 5),
+                record("Redis", 1, 5, 10),
+                record("Postgres", 1, 5, 30),
+                record("Routes", 2, 35, 1),
+            ],
+            Vec::new(),
+            None,
+            boot,
+        );
+
+        let path = report
+            .critical_path()
+            .into_iter()
+            .map(|prepare| prepare.name)
+            .collect::<Vec<_>>();
+        assert_eq!(path, ["Config", "Postgres", "Routes"]);
+        assert_eq!(report.prepares[1].offset, Duration::from_millis(5));
+        assert!(report.to_string().contains("* Postgres"));
+        assert!(!report.to_string().contains("profile"));
+    }
+
+    #[test]
+    fn test_profile() {
+        let profile = ConfigProfiles::new(Conf::default())
+            .base_source("base.toml")
+            .overlay("dev", "dev.toml", |_| ())
+            .load("dev")
+            .0;
+        let report = BootReport::new(&[], Vec::new(), Some(profile), Instant::now());
+
+        assert!(report
+            .to_string()
+            .contains("profile dev [base.toml, dev.toml]"));
+    }
+
+    #[tokio::test]
+    async fn test_report_profile() {
+        let conf = ConfigProfiles::new(Conf::default()).load("prod");
+        let (prepare_fut, _) = ServerPrepare::with_config(conf)
+            .report_profile()
+            .prepares
+            .unwrap();
+        let (mut state, ..) = prepare_fut.await.unwrap().unwrap();
+
+        let profile = ReportProfile::take(&mut state).expect("profile reported");
+        assert!(profile.is("prod"));
+    }
+}
@@ -8,7 +8,7 @@ use tokio::net::TcpListener;
 
 /// binding address provided by [ServeAddress]
 pub trait BindServe: ServeAddress {
-    fn bind(&self) -> LocalBoxFuture<io::Result<TcpListener>> {
+    fn bind(&self) -> LocalBoxFuture<'_, io::Result<TcpListener>> {
         let addr = ServeAddress::get_address(self).into();
         TcpListener::bind(addr).boxed_local()
     }
@@ -41,4 +41,4 @@ pub trait ConfigureServerEffect {
     }
 }
 
-pub use super::decorator::{EmptyDecorator, PrepareDecorator};
+pub use super::decorator::{EmptyDecorator, PrepareDecorator, TimingDecorator};
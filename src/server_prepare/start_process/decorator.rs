@@ -1,5 +1,8 @@
 use crate::{Prepare, PrepareError, ServerPrepare};
-use futures::future::Ready;
+use futures::{
+    future::{LocalBoxFuture, Ready},
+    FutureExt,
+};
 use std::any::type_name;
 use std::convert::Infallible;
 use std::future::Future;
@@ -99,7 +102,11 @@ pub trait PrepareDecorator: 'static {
 pub struct EmptyDecorator;
 
 impl PrepareDecorator for EmptyDecorator {
-    type OutFut<Fut, T> = Fut where Fut: Future<Output=Result<T, PrepareError>> + 'static, T: 'static;
+    type OutFut<Fut, T>
+        = Fut
+    where
+        Fut: Future<Output = Result<T, PrepareError>> + 'static,
+        T: 'static;
 
     fn decorator<Fut, T>(&self, _src: &'static str, in_fut: Fut) -> Self::OutFut<Fut, T>
     where
@@ -109,3 +116,60 @@ impl PrepareDecorator for EmptyDecorator {
         in_fut
     }
 }
+
+/// [PrepareDecorator] logging how long each prepare take at info level (with `logger` feature),
+/// it can wrap another [`PrepareDecorator`]
+///
+/// the timing of all prepares is also collected in the [`BootReport`](crate::BootReport)
+pub struct TimingDecorator<D = EmptyDecorator>(D);
+
+impl Default for TimingDecorator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TimingDecorator {
+    pub fn new() -> Self {
+        Self(EmptyDecorator)
+    }
+}
+
+impl<D: PrepareDecorator> TimingDecorator<D> {
+    /// timing the prepare decorated by `inner`
+    pub fn wrap(inner: D) -> Self {
+        Self(inner)
+    }
+}
+
+impl<D: PrepareDecorator> PrepareDecorator for TimingDecorator<D> {
+    type OutFut<Fut, T>
+        = LocalBoxFuture<'static, Result<T, PrepareError>>
+    where
+        Fut: Future<Output = Result<T, PrepareError>> + 'static,
+        T: 'static;
+
+    fn decorator<Fut, T>(&self, src: &'static str, in_fut: Fut) -> Self::OutFut<Fut, T>
+    where
+        Fut: Future<Output = Result<T, PrepareError>> + 'static,
+        T: 'static,
+    {
+        let fut = self.0.decorator(src, in_fut);
+        async move {
+            #[cfg(feature = "logger")]
+            let started = std::time::Instant::now();
+            let ret = fut.await;
+            #[cfg(feature = "logger")]
+            match &ret {
+                Ok(_) => {
+                    info!(prepare = src, elapsed = ?started.elapsed(), "Prepare Done");
+                }
+                Err(err) => {
+                    warn!(prepare = src, elapsed = ?started.elapsed(), error = %err, "Prepare Failed");
+                }
+            }
+            ret
+        }
+        .boxed_local()
+    }
+}
@@ -22,11 +22,16 @@ where
             t
         })?;
 
+        // the span created before the subscriber initialized is disabled
+        #[cfg(feature = "logger")]
+        let span = tracing::debug_span!("prepare server start");
+        #[cfg(not(feature = "logger"))]
+        let span = self.span;
         Ok(ServerPrepare::new(
             self.prepares,
             self.graceful,
             self.state,
-            self.span,
+            span,
         ))
     }
 }
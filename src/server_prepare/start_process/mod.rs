@@ -1,8 +1,10 @@
 mod adding_middleware;
 mod adding_prepare;
+pub mod boot_report;
 pub mod configure;
 mod decorator;
 pub mod graceful_shutdown;
 pub(super) mod logger;
 mod post_prepare;
+pub mod schedule;
 pub mod state_ready;
@@ -24,7 +24,18 @@ where
     T: PostPrepare<S, Args> + Send + 'static,
 {
     Box::new(move |s: S| {
-        Box::pin(async move { <T as PostPrepare<S, Args>>::exec(prepare, &s).await })
+        let fut = async move { <T as PostPrepare<S, Args>>::exec(prepare, &s).await };
+        // the span is created before spawn, thus it is the child of the server span
+        #[cfg(feature = "logger")]
+        let fut = tracing::Instrument::instrument(
+            fut,
+            tracing::info_span!(
+                "post_prepare",
+                otel.name = std::any::type_name::<T>(),
+                post_prepare = std::any::type_name::<T>(),
+            ),
+        );
+        Box::pin(fut)
     })
 }
 
@@ -50,21 +61,10 @@ macro_rules! post_prepare_gen {
         }
     };
 }
-post_prepare_gen!();
-post_prepare_gen!(A0);
-post_prepare_gen!(A0, A1);
-post_prepare_gen!(A0, A1, A2);
-post_prepare_gen!(A0, A1, A2, A3);
-post_prepare_gen!(A0, A1, A2, A3, A4);
-post_prepare_gen!(A0, A1, A2, A3, A4, A5);
-post_prepare_gen!(A0, A1, A2, A3, A4, A5, A6);
-post_prepare_gen!(A0, A1, A2, A3, A4, A5, A6, A7);
-post_prepare_gen!(A0, A1, A2, A3, A4, A5, A6, A7, A8);
-post_prepare_gen!(A0, A1, A2, A3, A4, A5, A6, A7, A8, A9);
-post_prepare_gen!(A0, A1, A2, A3, A4, A5, A6, A7, A8, A9, A10);
+all_tuples!(post_prepare_gen);
 
-impl<C, Effect, Log, State, Graceful, Decorator>
-    ServerPrepare<C, Effect, Log, StateReady<State>, Graceful, Decorator>
+impl<C, Effect, Log, State, W, Graceful, Decorator>
+    ServerPrepare<C, Effect, Log, StateReady<State, W>, Graceful, Decorator>
 {
     /// execute a task after all prepare task done before service start
     ///
@@ -0,0 +1,229 @@
+use std::{panic::AssertUnwindSafe, time::Duration};
+
+use futures::{
+    future::{join_all, select, BoxFuture, Either},
+    Future, FutureExt,
+};
+use tokio::{
+    task::JoinHandle,
+    time::{Instant, MissedTickBehavior},
+};
+
+use crate::{worker::Shutdown, ServerPrepare};
+
+use super::{post_prepare::PostPrepare, state_ready::StateReady};
+
+enum ScheduleKind {
+    Interval(Duration),
+    #[cfg(feature = "cron")]
+    Cron(Box<cron::Schedule>),
+}
+
+/// when to run the job registered by [`ServerPrepare::schedule`]
+///
+/// - a fixed interval, the [Duration] can be converted into it
+/// - a cron expression with seconds field, like `0 */5 * * * *`, require `cron` feature
+pub struct Schedule {
+    kind: ScheduleKind,
+    overlap: bool,
+}
+
+impl From<Duration> for Schedule {
+    fn from(period: Duration) -> Self {
+        Self::every(period)
+    }
+}
+
+impl Schedule {
+    /// run every `period`, the first run is after `period`
+    pub fn every(period: Duration) -> Self {
+        Self {
+            kind: ScheduleKind::Interval(period),
+            overlap: false,
+        }
+    }
+
+    /// run at the time matching the cron expression, in UTC
+    #[cfg(feature = "cron")]
+    pub fn cron(expr: &str) -> Result<Self, cron::error::Error> {
+        Ok(Self {
+            kind: ScheduleKind::Cron(Box::new(expr.parse()?)),
+            overlap: false,
+        })
+    }
+
+    /// start the next run even if the previous one has not finished
+    ///
+    /// by default, the runs never overlap, the ticks during a run are skipped
+    pub fn allow_overlap(mut self) -> Self {
+        self.overlap = true;
+        self
+    }
+}
+
+/// the ticks of a [Schedule]
+enum Ticker {
+    Interval(tokio::time::Interval),
+    #[cfg(feature = "cron")]
+    Cron(Box<cron::Schedule>),
+}
+
+impl Ticker {
+    fn new(kind: ScheduleKind) -> Self {
+        match kind {
+            ScheduleKind::Interval(period) => {
+                let mut ticker = tokio::time::interval_at(Instant::now() + period, period);
+                ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                Ticker::Interval(ticker)
+            }
+            #[cfg(feature = "cron")]
+            ScheduleKind::Cron(schedule) => Ticker::Cron(schedule),
+        }
+    }
+
+    /// wait for the next tick, [None] if no more tick
+    async fn tick(&mut self) -> Option<()> {
+        match self {
+            Ticker::Interval(ticker) => {
+                ticker.tick().await;
+                Some(())
+            }
+            #[cfg(feature = "cron")]
+            Ticker::Cron(schedule) => {
+                let next = schedule.upcoming(chrono::Utc).next()?;
+                let delay = (next - chrono::Utc::now()).to_std().unwrap_or_default();
+                tokio::time::sleep(delay).await;
+                Some(())
+            }
+        }
+    }
+}
+
+/// a panic job should not stop the following runs
+#[cfg_attr(not(feature = "logger"), allow(unused_variables))]
+async fn run_caught(run: impl Future<Output = ()>) {
+    if let Err(payload) = AssertUnwindSafe(run).catch_unwind().await {
+        warn!(
+            reason = crate::effect_utils::worker::panic_message(&*payload),
+            "Scheduled Job Panic"
+        );
+    }
+}
+
+pub type ScheduleFn<S> = Box<dyn FnOnce(S, Shutdown) -> BoxFuture<'static, ()> + Send>;
+
+fn schedule_to_dyn<S, Args, T>(schedule: Schedule, job: T) -> ScheduleFn<S>
+where
+    S: Send + Sync + 'static,
+    Args: Send + 'static,
+    T: PostPrepare<S, Args> + Clone + Send + 'static,
+{
+    Box::new(move |state: S, mut shutdown: Shutdown| {
+        Box::pin(async move {
+            let mut ticker = Ticker::new(schedule.kind);
+            let mut running = Vec::new();
+            loop {
+                let tick = select(ticker.tick().boxed(), shutdown.wait().boxed()).await;
+                let Either::Left((Some(()), _)) = tick else {
+                    break;
+                };
+                let run = run_caught(job.clone().exec(&state));
+                if schedule.overlap {
+                    running.retain(|handle: &JoinHandle<()>| !handle.is_finished());
+                    running.push(tokio::spawn(run));
+                } else {
+                    // let the running job finish even if shutdown
+                    run.await;
+                }
+            }
+            join_all(running).await;
+        })
+    })
+}
+
+impl<C, Effect, Log, State, W, Graceful, Decorator>
+    ServerPrepare<C, Effect, Log, StateReady<State, W>, Graceful, Decorator>
+{
+    /// run a job periodically after the server start
+    ///
+    /// the job is the same as [`ServerPrepare::post_prepare`], with arguments extracted from State by `FromRef`,
+    /// but need impl [Clone]. The job stops when the graceful shutdown signal arrive,
+    /// a running job will be waited for
+    ///
+    /// ## Note
+    /// the job is not run by `preparing_test`
+    pub fn schedule<Args, T>(mut self, schedule: impl Into<Schedule>, job: T) -> Self
+    where
+        T: PostPrepare<State, Args> + Clone + Send + 'static,
+        State: Send + 'static + Sync,
+        Args: Send + 'static,
+    {
+        self.state
+            .push_schedule(schedule_to_dyn(schedule.into(), job));
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio::sync::watch;
+
+    use super::{schedule_to_dyn, Schedule};
+    use crate::worker::Shutdown;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_schedule_stop() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let job = |runs: Arc<AtomicUsize>| async move {
+            runs.fetch_add(1, Ordering::SeqCst);
+        };
+        let (sender, receiver) = watch::channel(false);
+        let task = tokio::spawn(schedule_to_dyn(
+            Schedule::every(Duration::from_millis(5)),
+            job,
+        )(Arc::clone(&runs), Shutdown::new(receiver)));
+
+        tokio::time::sleep(Duration::from_millis(32)).await;
+        sender.send_replace(true);
+        task.await.unwrap();
+
+        let stopped = runs.load(Ordering::SeqCst);
+        assert_eq!(stopped, 6);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), stopped);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_schedule_panic() {
+        let runs = Arc::new(AtomicUsize::new(0));
+        let job = |runs: Arc<AtomicUsize>| async move {
+            if runs.fetch_add(1, Ordering::SeqCst).is_multiple_of(2) {
+                panic!("boom");
+            }
+        };
+        for schedule in [
+            Schedule::every(Duration::from_millis(5)),
+            Schedule::every(Duration::from_millis(5)).allow_overlap(),
+        ] {
+            runs.store(0, Ordering::SeqCst);
+            let (sender, receiver) = watch::channel(false);
+            let task = tokio::spawn(schedule_to_dyn(schedule, job)(
+                Arc::clone(&runs),
+                Shutdown::new(receiver),
+            ));
+
+            tokio::time::sleep(Duration::from_millis(32)).await;
+            sender.send_replace(true);
+            task.await.unwrap();
+            assert_eq!(runs.load(Ordering::SeqCst), 6);
+        }
+    }
+}
@@ -1,26 +1,164 @@
 use std::marker::PhantomData;
 
-use crate::{prepare_behave::FromStateCollector, ServerPrepare};
+use crate::{
+    effect_utils::{
+        health::Health,
+        worker::{take_workers, Workers},
+    },
+    prepare_behave::{
+        effect_collectors::{
+            state_collector::{DuplicatePolicy, StateCheck},
+            state_list::{ContainsAll, ProduceState, RequireState, StateCons, StateList, StateNil},
+        },
+        FromStateCollector, StateCollector,
+    },
+    PrepareStartError, ServerPrepare,
+};
 
-use super::post_prepare::PostPrepareFn;
+use super::{post_prepare::PostPrepareFn, schedule::ScheduleFn};
 
 pub struct StateNotReady;
 
-pub struct StateReady<S>(Vec<PostPrepareFn<S>>);
+type AssumeStateRet<C, FutEffect, Log, State, T, L, Graceful, Decorator> = ServerPrepare<
+    C,
+    FutEffect,
+    Log,
+    StateReady<State, Checked<StateCons<T, L>>>,
+    Graceful,
+    Decorator,
+>;
 
-impl<S> Default for StateReady<S> {
+/// the produced state types are tracked in type level list `L`,
+/// see [`ServerPrepare::typed_state`]
+pub struct TypedState<L>(PhantomData<L>);
+
+/// the state wiring is not checked
+pub struct Unchecked;
+
+/// the state wiring is checked with the produced state types `L`
+pub struct Checked<L>(PhantomData<L>);
+
+pub struct StateReady<S, W = Unchecked> {
+    post_prepares: Vec<PostPrepareFn<S>>,
+    schedules: Vec<ScheduleFn<S>>,
+    duplicate: DuplicatePolicy,
+    check: StateCheck,
+    _wiring: PhantomData<W>,
+}
+
+impl<S, W> Default for StateReady<S, W> {
     fn default() -> Self {
-        Self(vec![])
+        Self {
+            post_prepares: vec![],
+            schedules: vec![],
+            duplicate: DuplicatePolicy::default(),
+            check: StateCheck::default(),
+            _wiring: PhantomData,
+        }
     }
 }
 
-impl<S> StateReady<S> {
+impl<S, W> StateReady<S, W> {
     pub fn push(&mut self, post_prepare: PostPrepareFn<S>) {
-        self.0.push(post_prepare)
+        self.post_prepares.push(post_prepare)
+    }
+
+    pub fn push_schedule(&mut self, schedule: ScheduleFn<S>) {
+        self.schedules.push(schedule)
+    }
+
+    /// the post prepare tasks and the scheduled jobs
+    pub fn take(self) -> (Vec<PostPrepareFn<S>>, Vec<ScheduleFn<S>>) {
+        (self.post_prepares, self.schedules)
+    }
+
+    fn rewire<W2>(self) -> StateReady<S, W2> {
+        StateReady {
+            post_prepares: self.post_prepares,
+            schedules: self.schedules,
+            duplicate: self.duplicate,
+            check: self.check,
+            _wiring: PhantomData,
+        }
+    }
+
+    /// check the collector then convert it to the State, with the [`Worker`](crate::worker::Worker)s
+    /// registered for the State
+    pub(crate) fn fetch_state(
+        &self,
+        collector: StateCollector,
+    ) -> Result<(S, Option<Workers<S>>, Health), PrepareStartError>
+    where
+        S: FromStateCollector + 'static,
+    {
+        self.duplicate.check(&collector)?;
+        let mut collector = collector;
+        // the State can always hold the `Health`, even no check registered
+        let health = Health::from_collector(&mut collector);
+        let workers = take_workers::<S>(&mut collector)?;
+        let state = match self.check {
+            StateCheck::Ignore => S::fetch(collector)?,
+            // the collector is still needed for checking the left states
+            _ => {
+                let state = S::fetch_mut(&mut collector)?;
+                // not required by the State
+                let _ = collector.get::<Health>();
+                self.check.check(&collector)?;
+                state
+            }
+        };
+        Ok((state, workers, health))
+    }
+}
+
+/// whether the State `S` can be built from the produced state types, `I` is the type level index
+pub trait StateWiring<S, I> {}
+
+impl<S> StateWiring<S, ()> for Unchecked {}
+
+impl<S, L, I> StateWiring<S, I> for Checked<L>
+where
+    S: RequireState,
+    L: ContainsAll<S::Required, I>,
+{
+}
+
+/// tracking the state types produced by the effect `E`
+pub trait TrackState<E> {
+    type Tracked;
+
+    fn track(self) -> Self::Tracked;
+}
+
+impl<E> TrackState<E> for StateNotReady {
+    type Tracked = Self;
+
+    fn track(self) -> Self::Tracked {
+        self
+    }
+}
+
+impl<E, S> TrackState<E> for StateReady<S, Unchecked> {
+    type Tracked = Self;
+
+    fn track(self) -> Self::Tracked {
+        self
     }
+}
 
-    pub fn take(self) -> Vec<PostPrepareFn<S>> {
-        self.0
+impl<E: ProduceState, L: StateList> TrackState<E> for TypedState<L> {
+    type Tracked = TypedState<<E::Produced as StateList>::Append<L>>;
+
+    fn track(self) -> Self::Tracked {
+        TypedState(PhantomData)
+    }
+}
+
+impl<E: ProduceState, S, L: StateList> TrackState<E> for StateReady<S, Checked<L>> {
+    type Tracked = StateReady<S, Checked<<E::Produced as StateList>::Append<L>>>;
+
+    fn track(self) -> Self::Tracked {
+        self.rewire()
     }
 }
 
@@ -44,4 +182,168 @@ impl<C, FutEffect, Log, Graceful, Decorator>
     pub fn no_state(self) -> ServerPrepare<C, FutEffect, Log, StateReady<()>, Graceful, Decorator> {
         self.convert_state::<()>()
     }
+
+    /// tracking the state types produced by [`ServerPrepare::prepare_state`] in type level,
+    /// thus `preparing` fails to compile if the State require a type no prepare produces
+    ///
+    /// - the effect of [`ServerPrepare::prepare_state`] need impl [`ProduceState`], for instance
+    ///   [`AddState`](crate::state::AddState) or the tuple of them, thus the prepare should return the concrete type
+    /// - the State need impl [`RequireState`], it is generated by [`FromStateCollector`](axum_starter_macro::FromStateCollector) derive
+    /// - the state added by middleware, concurrent prepares or with a key is not tracked,
+    ///   using [`ServerPrepare::assume_state`] to declare it
+    /// - the [`Health`] is always produced
+    ///
+    /// ## Example
+    ///
+    /// ```rust,no_run
+    /// use std::net::SocketAddr;
+    ///
+    /// use axum_starter::{
+    ///     prepare, state::AddState, Configure, FromStateCollector, Provider, ServerPrepare,
+    /// };
+    ///
+    /// #[derive(Debug, Provider, Configure)]
+    /// #[conf(address(provide), server)]
+    /// struct Config {
+    ///     #[provider(transparent)]
+    ///     addr: SocketAddr,
+    /// }
+    ///
+    /// #[derive(Clone, FromStateCollector)]
+    /// struct AppState {
+    ///     count: u8,
+    /// }
+    ///
+    /// #[prepare(AddCount)]
+    /// fn add_count() -> AddState<u8> {
+    ///     AddState(1)
+    /// }
+    ///
+    /// async fn start(config: Config) {
+    ///     let _ready = ServerPrepare::with_config(config)
+    ///         .typed_state()
+    ///         .prepare_state(AddCount)
+    ///         .convert_state::<AppState>()
+    ///         .preparing()
+    ///         .await;
+    /// }
+    /// ```
+    ///
+    /// without the prepare producing `u8`, it fails to compile
+    ///
+    /// ```rust,compile_fail
+    /// # use std::net::SocketAddr;
+    /// # use axum_starter::{Configure, FromStateCollector, Provider, ServerPrepare};
+    /// # #[derive(Debug, Provider, Configure)]
+    /// # #[conf(address(provide), server)]
+    /// # struct Config {
+    /// #     #[provider(transparent)]
+    /// #     addr: SocketAddr,
+    /// # }
+    /// #[derive(Clone, FromStateCollector)]
+    /// struct AppState {
+    ///     count: u8,
+    /// }
+    ///
+    /// async fn start(config: Config) {
+    ///     let _ready = ServerPrepare::with_config(config)
+    ///         .typed_state()
+    ///         .convert_state::<AppState>()
+    ///         .preparing()
+    ///         .await;
+    /// }
+    /// ```
+    pub fn typed_state(
+        self,
+    ) -> ServerPrepare<
+        C,
+        FutEffect,
+        Log,
+        TypedState<StateCons<Health, StateNil>>,
+        Graceful,
+        Decorator,
+    > {
+        ServerPrepare {
+            prepares: self.prepares,
+            graceful: self.graceful,
+            state: TypedState(PhantomData),
+            span: self.span,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<C, FutEffect, Log, L, Graceful, Decorator>
+    ServerPrepare<C, FutEffect, Log, TypedState<L>, Graceful, Decorator>
+{
+    /// convert internal [`StateCollector`](crate::StateCollector) to special
+    /// State, the state wiring is checked at `preparing`
+    pub fn convert_state<S: FromStateCollector>(
+        self,
+    ) -> ServerPrepare<C, FutEffect, Log, StateReady<S, Checked<L>>, Graceful, Decorator> {
+        ServerPrepare {
+            prepares: self.prepares,
+            graceful: self.graceful,
+            state: StateReady::default(),
+            span: self.span,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// convenient function for [`ServerPrepare::convert_state::<()>`](axum_starter::ServerPrepare::convert_state)
+    pub fn no_state(
+        self,
+    ) -> ServerPrepare<C, FutEffect, Log, StateReady<(), Checked<L>>, Graceful, Decorator> {
+        self.convert_state::<()>()
+    }
+
+    /// declare the state type `T` is produced, which is not tracked automatically
+    pub fn assume_state<T>(
+        self,
+    ) -> ServerPrepare<C, FutEffect, Log, TypedState<StateCons<T, L>>, Graceful, Decorator> {
+        ServerPrepare {
+            prepares: self.prepares,
+            graceful: self.graceful,
+            state: TypedState(PhantomData),
+            span: self.span,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<C, FutEffect, Log, State, L, Graceful, Decorator>
+    ServerPrepare<C, FutEffect, Log, StateReady<State, Checked<L>>, Graceful, Decorator>
+{
+    /// declare the state type `T` is produced, which is not tracked automatically
+    pub fn assume_state<T>(
+        self,
+    ) -> AssumeStateRet<C, FutEffect, Log, State, T, L, Graceful, Decorator> {
+        ServerPrepare {
+            prepares: self.prepares,
+            graceful: self.graceful,
+            state: self.state.rewire(),
+            span: self.span,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<C, FutEffect, Log, State, W, Graceful, Decorator>
+    ServerPrepare<C, FutEffect, Log, StateReady<State, W>, Graceful, Decorator>
+{
+    /// set how to handle the state inserted more than once, default is [`DuplicatePolicy::Overwrite`]
+    pub fn duplicate_state(mut self, policy: DuplicatePolicy) -> Self {
+        self.state.duplicate = policy;
+        self
+    }
+
+    /// check whether all state inserted are consumed by the State, and no state is overwritten
+    ///
+    /// the check happens at `preparing`, with [`StateCheck::Strict`] the wiring mistakes,
+    /// like a prepare's output is never used, will reject starting the server.
+    /// Checking requires the State fetched by [`FromStateCollector::fetch_mut`] rather than `fetch`
+    pub fn state_check(mut self, check: StateCheck) -> Self {
+        self.state.check = check;
+        self
+    }
 }
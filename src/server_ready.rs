@@ -11,10 +11,12 @@ impl<S: Future<Output = io::Result<()>>, G: Future<Output = io::Result<()>>> Ser
     /// start this server
     pub async fn launch(self) -> io::Result<()> {
         info!(service.status = "Starting");
-        match self {
-            ServerReady::Server(s) => s.await?,
-            ServerReady::Graceful(g) => g.await?,
+        let ret = match self {
+            ServerReady::Server(s) => s.await,
+            ServerReady::Graceful(g) => g.await,
         };
-        Ok(())
+        #[cfg(feature = "otlp")]
+        crate::otlp::shutdown().await;
+        ret
     }
 }
@@ -0,0 +1,205 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
+
+use futures::future::{ready, Ready};
+use tracing_subscriber::{
+    filter::{EnvFilter, ParseError},
+    fmt,
+    layer::SubscriberExt,
+    reload,
+    util::{SubscriberInitExt, TryInitError},
+    Registry,
+};
+
+use crate::{state::AddState, Prepare};
+
+static HANDLE: OnceLock<LogReloadHandle> = OnceLock::new();
+
+/// the output format of [SubscriberLogger]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    #[default]
+    Full,
+    Compact,
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = SubscriberError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "full" => Ok(LogFormat::Full),
+            "compact" => Ok(LogFormat::Compact),
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err(SubscriberError::UnknownFormat(s.to_owned())),
+        }
+    }
+}
+
+/// the value provided by the config for `level_from` or `format_from` of
+/// `#[conf(logger(tracing_subscriber(...)))]`, [None] keep the default
+pub trait LogSetting {
+    fn into_setting(self) -> Option<String>;
+}
+
+impl LogSetting for String {
+    fn into_setting(self) -> Option<String> {
+        Some(self)
+    }
+}
+
+impl LogSetting for &str {
+    fn into_setting(self) -> Option<String> {
+        Some(self.to_owned())
+    }
+}
+
+impl LogSetting for &String {
+    fn into_setting(self) -> Option<String> {
+        Some(self.clone())
+    }
+}
+
+impl<T: LogSetting> LogSetting for Option<T> {
+    fn into_setting(self) -> Option<String> {
+        self.and_then(LogSetting::into_setting)
+    }
+}
+
+impl<'a, T> LogSetting for &'a Option<T>
+where
+    &'a T: LogSetting,
+{
+    fn into_setting(self) -> Option<String> {
+        self.as_ref().and_then(LogSetting::into_setting)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+/// init or reload [SubscriberLogger] failure
+pub enum SubscriberError {
+    #[error("invalid filter directives: {0}")]
+    Directives(#[from] ParseError),
+    #[error("unknown log format `{0}`, expect one of `full`, `compact`, `pretty` or `json`")]
+    UnknownFormat(String),
+    #[error(transparent)]
+    Init(#[from] TryInitError),
+    #[error("reload filter failure: {0}")]
+    Reload(#[from] reload::Error),
+    #[error("the `SubscriberLogger` is not initialized")]
+    NotInitialized,
+}
+
+/// the `tracing-subscriber` logger with reloadable filter directives, require `subscriber` feature
+///
+/// it can be selected by `#[conf(logger(tracing_subscriber(...)))]` on
+/// [`Configure`](axum_starter_macro::Configure) derive, and the filter can be changed at runtime
+/// by the [LogReloadHandle]
+#[derive(Debug, Clone)]
+pub struct SubscriberLogger {
+    directives: String,
+    format: LogFormat,
+}
+
+impl Default for SubscriberLogger {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SubscriberLogger {
+    /// the `info` level in [LogFormat::Full] format
+    pub fn new() -> Self {
+        Self {
+            directives: String::from("info"),
+            format: LogFormat::Full,
+        }
+    }
+
+    /// the filter directives, like `info,sqlx=warn`
+    pub fn directives(mut self, directives: impl Into<String>) -> Self {
+        self.directives = directives.into();
+        self
+    }
+
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// init as the global subscriber
+    pub fn init(&self) -> Result<(), SubscriberError> {
+        let (filter, handle) = reload::Layer::new(EnvFilter::try_new(&self.directives)?);
+        let registry = tracing_subscriber::registry().with(filter);
+        match self.format {
+            LogFormat::Full => registry.with(fmt::layer()).try_init(),
+            LogFormat::Compact => registry.with(fmt::layer().compact()).try_init(),
+            LogFormat::Pretty => registry.with(fmt::layer().pretty()).try_init(),
+            LogFormat::Json => registry.with(fmt::layer().json()).try_init(),
+        }?;
+        let _ = HANDLE.set(LogReloadHandle(handle));
+        Ok(())
+    }
+}
+
+/// change the filter directives of [SubscriberLogger] at runtime
+///
+/// adding it into the State by [LogReload] prepare
+#[derive(Clone)]
+pub struct LogReloadHandle(reload::Handle<EnvFilter, Registry>);
+
+impl LogReloadHandle {
+    /// the handle of the initialized [SubscriberLogger]
+    pub fn get() -> Option<Self> {
+        HANDLE.get().cloned()
+    }
+
+    /// replace the filter directives, like `debug,hyper=info`
+    pub fn reload(&self, directives: &str) -> Result<(), SubscriberError> {
+        let filter = EnvFilter::try_new(directives)?;
+        self.0.reload(filter)?;
+        info!(log.directives = directives, "Log Filter Reloaded");
+        Ok(())
+    }
+
+    /// the current filter directives
+    pub fn directives(&self) -> Option<String> {
+        self.0.with_current(|filter| filter.to_string()).ok()
+    }
+}
+
+/// [Prepare] adding the [LogReloadHandle] into the State, require [SubscriberLogger] initialized
+pub struct LogReload;
+
+impl<C: 'static> Prepare<C> for LogReload {
+    type Effect = AddState<LogReloadHandle>;
+    type Error = SubscriberError;
+    type Future = Ready<Result<Self::Effect, Self::Error>>;
+
+    fn prepare(self, _: Arc<C>) -> Self::Future {
+        ready(
+            LogReloadHandle::get()
+                .map(AddState::new)
+                .ok_or(SubscriberError::NotInitialized),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{LogFormat, SubscriberError};
+
+    #[test]
+    fn test_log_format() {
+        assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+        assert!(matches!(
+            "yaml".parse::<LogFormat>(),
+            Err(SubscriberError::UnknownFormat(_))
+        ));
+    }
+}
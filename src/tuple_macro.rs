@@ -0,0 +1,25 @@
+/// invoke the macro `$m` with the tuple type params of each arity, from `()` to
+/// 16 elements
+///
+/// all the traits implemented on tuples use it, thus their max arity is the same
+macro_rules! all_tuples {
+    ($m:ident) => {
+        $m!();
+        $m!(T1);
+        $m!(T1, T2);
+        $m!(T1, T2, T3);
+        $m!(T1, T2, T3, T4);
+        $m!(T1, T2, T3, T4, T5);
+        $m!(T1, T2, T3, T4, T5, T6);
+        $m!(T1, T2, T3, T4, T5, T6, T7);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+        $m!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16);
+    };
+}